@@ -3,6 +3,7 @@
 //! Contains API errors along with result types.
 
 use std::{fmt, io};
+use std::time::Duration;
 use std::result::Result as StdResult;
 use std::error::Error as StdError;
 
@@ -24,6 +25,8 @@ pub enum Error {
     FromDTO(FromDTOError),
     /// JSON decode error.
     JSONDecode(json::DecoderError),
+    /// JSON encode error.
+    JSONEncode(json::EncoderError),
     /// Forbidden.
     Forbidden(String),
     /// Bad request
@@ -33,23 +36,156 @@ pub enum Error {
     /// Not found
     NotFound(String),
     /// Internal server error.
-    Server(String),
+    Server {
+        /// Machine readable code of the error, if the server sent it.
+        code: Option<String>,
+        /// Message of the error.
+        message: String,
+    },
     /// The token type is not valid.
     InvalidTokenType,
     /// The scope is not valid.
     InvalidScope,
     /// The secret is not valid.
     InvalidSecret,
+    /// The URL is not valid.
+    InvalidUrl,
     /// Registration error.
-    Registration,
+    Registration(RegistrationError),
     /// An error occurred generating a transaction.
     Transaction,
     /// Connection confirmation error.
     ConfirmConnection,
     /// Accepted
     Accepted(String),
-    /// Unauthorized
+    /// Unauthorized, the credentials are not valid or have expired.
     Unauthorized(String),
+    /// The operation timed out.
+    Timeout,
+    /// The response is bigger than the maximum response size of the client.
+    ResponseTooLarge,
+    /// The service is temporarily unavailable, for example, for maintenance. It contains the time
+    /// to wait before retrying, if the server sent it.
+    Unavailable(Option<Duration>),
+    /// The server redirected the request more times than the maximum allowed by the client.
+    TooManyRedirects,
+    /// The circuit breaker of the client is open after too many consecutive failures, so the
+    /// request was not sent.
+    CircuitOpen,
+    /// The operation was performed too recently, so it was not sent to the server. It contains the
+    /// time to wait before retrying.
+    RateLimited(Duration),
+}
+
+impl Error {
+    /// Gets the message sent by the server, if the error carries one.
+    ///
+    /// This allows showing the message of the server to the user regardless of the status code
+    /// of the response.
+    pub fn message(&self) -> Option<&str> {
+        match *self {
+            Error::Forbidden(ref m) |
+            Error::BadRequest(ref m) |
+            Error::Client(ref m) |
+            Error::NotFound(ref m) |
+            Error::Accepted(ref m) |
+            Error::Unauthorized(ref m) |
+            Error::Server { message: ref m, .. } => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Gets the machine readable code of the error sent by the server, if any.
+    ///
+    /// This allows branching on the specific error without matching the human readable message,
+    /// that might change.
+    pub fn code(&self) -> Option<&str> {
+        match *self {
+            Error::Server { code: Some(ref c), .. } => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Gets the HTTP status code that best represents the error.
+    ///
+    /// This is useful for gateways built on top of the client, that need to translate the errors
+    /// back into HTTP responses. Errors caused by the server or by the connection to it are
+    /// represented as gateway errors.
+    pub fn status_code(&self) -> u16 {
+        match *self {
+            Error::Accepted(_) => 202,
+            Error::BadRequest(_) |
+            Error::Client(_) |
+            Error::InvalidScope |
+            Error::Registration(RegistrationError::WeakPassword) => 400,
+            Error::Unauthorized(_) |
+            Error::InvalidTokenType |
+            Error::InvalidSecret => 401,
+            Error::Forbidden(_) => 403,
+            Error::NotFound(_) => 404,
+            Error::Registration(_) => 409,
+            Error::RateLimited(_) => 429,
+            Error::JSONEncode(_) |
+            Error::InvalidUrl |
+            Error::Server { .. } |
+            Error::Transaction |
+            Error::ConfirmConnection => 500,
+            Error::Hyper(_) |
+            Error::IO(_) |
+            Error::FromDTO(_) |
+            Error::JSONDecode(_) |
+            Error::ResponseTooLarge |
+            Error::TooManyRedirects => 502,
+            Error::Unavailable(_) |
+            Error::CircuitOpen => 503,
+            Error::Timeout => 504,
+        }
+    }
+}
+
+/// The error type of the user registration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistrationError {
+    /// The email is already registered.
+    EmailTaken,
+    /// The username is already taken.
+    UsernameTaken,
+    /// The password is too weak.
+    WeakPassword,
+}
+
+impl RegistrationError {
+    /// Gets the registration error from the message sent by the server, if it's a known one.
+    pub fn from_message<S: AsRef<str>>(message: S) -> Option<RegistrationError> {
+        let message = message.as_ref().to_lowercase();
+        let taken = message.contains("taken") || message.contains("already") ||
+                    message.contains("exists");
+        if message.contains("email") && taken {
+            Some(RegistrationError::EmailTaken)
+        } else if message.contains("username") && taken {
+            Some(RegistrationError::UsernameTaken)
+        } else if message.contains("password") &&
+                  (message.contains("weak") || message.contains("insecure")) {
+            Some(RegistrationError::WeakPassword)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the description of the registration error.
+    pub fn description(&self) -> &str {
+        match *self {
+            RegistrationError::EmailTaken => "the email is already registered",
+            RegistrationError::UsernameTaken => "the username is already taken",
+            RegistrationError::WeakPassword => "the password is too weak",
+        }
+    }
+}
+
+impl fmt::Display for RegistrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
 }
 
 impl From<HyperError> for Error {
@@ -70,6 +206,12 @@ impl From<json::DecoderError> for Error {
     }
 }
 
+impl From<json::EncoderError> for Error {
+    fn from(error: json::EncoderError) -> Error {
+        Error::JSONEncode(error)
+    }
+}
+
 impl From<FromDTOError> for Error {
     fn from(error: FromDTOError) -> Error {
         Error::FromDTO(error)
@@ -89,19 +231,27 @@ impl StdError for Error {
             Error::IO(ref e) => e.description(),
             Error::FromDTO(ref e) => e.description(),
             Error::JSONDecode(ref e) => e.description(),
+            Error::JSONEncode(ref e) => e.description(),
             Error::Forbidden(ref e) |
             Error::BadRequest(ref e) |
             Error::Client(ref e) |
             Error::NotFound(ref e) |
             Error::Accepted(ref e) |
             Error::Unauthorized(ref e) |
-            Error::Server(ref e) => e,
+            Error::Server { message: ref e, .. } => e,
             Error::Transaction => "error generating transaction",
-            Error::Registration => "error registering user",
+            Error::Registration(ref e) => e.description(),
             Error::InvalidTokenType => "the provided token type is not a valid token type",
             Error::InvalidScope => "the provided scope is not a valid scope",
             Error::InvalidSecret => "the provided secret is not a valid secret",
+            Error::InvalidUrl => "the provided URL is not a valid URL",
             Error::ConfirmConnection => "error trying to confirm connection",
+            Error::Timeout => "the operation timed out",
+            Error::ResponseTooLarge => "the response is bigger than the maximum response size",
+            Error::Unavailable(_) => "the service is temporarily unavailable",
+            Error::TooManyRedirects => "the request was redirected too many times",
+            Error::CircuitOpen => "the circuit breaker is open after too many consecutive failures",
+            Error::RateLimited(_) => "the operation was performed too recently",
            
         }
     }
@@ -110,6 +260,9 @@ impl StdError for Error {
         match *self {
             Error::Hyper(ref e) => Some(e),
             Error::IO(ref e) => Some(e),
+            Error::FromDTO(ref e) => Some(e),
+            Error::JSONDecode(ref e) => Some(e),
+            Error::JSONEncode(ref e) => Some(e),
             _ => None,
         }
     }
@@ -24,10 +24,16 @@
 extern crate hyper;
 extern crate chrono;
 extern crate rustc_serialize;
+extern crate crypto;
+#[cfg(feature = "insecure-tls")]
+extern crate openssl;
 extern crate fractal_dto as dto;
 extern crate fractal_utils as utils;
 
 pub mod error;
 pub mod v1;
 
+/// Version of this client library.
+pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
 pub use v1::Client;
@@ -0,0 +1,91 @@
+//! Circuit breaker module for the Fractal API client.
+//!
+//! Contains the circuit breaker that makes the client fail fast while the server is failing, so
+//! that it doesn't get more load during an outage.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// State of the circuit breaker.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Requests are sent normally. Contains the number of consecutive failures.
+    Closed(u32),
+    /// Requests fail fast. Contains the time when the circuit was opened.
+    Open(Instant),
+    /// A single request is being sent to probe the server after the cooldown.
+    HalfOpen,
+}
+
+/// Circuit breaker of the client.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    /// Creates a new closed circuit breaker.
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold: failure_threshold,
+            cooldown: cooldown,
+            state: Mutex::new(State::Closed(0)),
+        }
+    }
+
+    /// Gets the failure threshold of the circuit breaker.
+    pub fn get_failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    /// Gets the cooldown of the circuit breaker.
+    pub fn get_cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    /// Checks if a request can be sent.
+    ///
+    /// Once the cooldown of an open circuit elapses, a single request will be allowed to probe the
+    /// server, and the rest will fail fast until its result is recorded.
+    pub fn allow_request(&self) -> bool {
+        let mut state = match self.state.lock() {
+            Ok(s) => s,
+            Err(_) => return true,
+        };
+        match *state {
+            State::Closed(_) => true,
+            State::Open(opened) => {
+                if opened.elapsed() >= self.cooldown {
+                    *state = State::HalfOpen;
+                    true
+                } else {
+                    false
+                }
+            }
+            State::HalfOpen => false,
+        }
+    }
+
+    /// Records a successful request, closing the circuit.
+    pub fn record_success(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = State::Closed(0);
+        }
+    }
+
+    /// Records a failed request, opening the circuit if the failure threshold is reached or if
+    /// the request was a probe.
+    pub fn record_failure(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = match *state {
+                State::Closed(failures) if failures + 1 < self.failure_threshold => {
+                    State::Closed(failures + 1)
+                }
+                State::Open(opened) => State::Open(opened),
+                _ => State::Open(Instant::now()),
+            };
+        }
+    }
+}
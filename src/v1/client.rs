@@ -1,25 +1,349 @@
 //use std::time::Duration;
+use std::env;
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
 
 use hyper::Client as HyperClient;
+use hyper::header::Headers;
+use hyper::client::RedirectPolicy;
+use hyper::client::pool::Config as PoolConfig;
+use hyper::Url;
+#[cfg(feature = "insecure-tls")]
+use openssl::ssl::{SslContext, SSL_VERIFY_NONE};
 
-use super::{Client, FRACTAL_SERVER, FRACTAL_DEV_SERVER};
+use super::{Client, FRACTAL_DEV_SERVER, DEFAULT_SERVER, DEFAULT_MAX_IDLE_CONNECTIONS,
+            DEFAULT_MAX_RESPONSE_SIZE, REQUEST_ID_HEADER, API_URL_ENV, APP_ID_ENV, APP_SECRET_ENV,
+            DEFAULT_MIN_AGE, DEFAULT_MAX_REDIRECTS, DEFAULT_MAX_ATTEMPTS};
+use super::transport::Transport;
+use super::circuit::CircuitBreaker;
+use error::{Result, Error};
 
 /// Client creation and modification.
 impl Client {
+    /// Creates a new Fractal Global Credits API client for the
+    /// [`DEFAULT_SERVER`](constant.DEFAULT_SERVER.html).
+    ///
+    /// This is the production server, unless the `dev` feature is enabled.
+    pub fn new() -> Client {
+        Client::build(DEFAULT_SERVER)
+    }
+
     /// Creates a new Fractal Global Credits API client.
-    pub fn new_with_url<S: AsRef<str>>(url: S) -> Client {
+    ///
+    /// An `Error::InvalidUrl` will be returned if the URL is not valid, so that misconfigurations
+    /// are detected when creating the client instead of when sending the first request.
+    pub fn new_with_url<S: AsRef<str>>(url: S) -> Result<Client> {
+        let url = url.as_ref();
+        if Url::parse(url).is_err() {
+            return Err(Error::InvalidUrl);
+        }
+        if url.ends_with('/') {
+            Ok(Client::build(url))
+        } else {
+            Ok(Client::build(&format!("{}/", url)))
+        }
+    }
+
+    /// Creates a client for the given server URL, that must be valid and end with a slash.
+    fn build(url: &str) -> Client {
         Client {
-            client: HyperClient::new(),
-            url: format!("{}v1/", url.as_ref()),
+            client: Client::build_hyper_client(DEFAULT_MAX_IDLE_CONNECTIONS, false),
+            transport: None,
+            url: format!("{}v1/", url),
+            max_idle_connections: DEFAULT_MAX_IDLE_CONNECTIONS,
+            accept_invalid_certs: false,
+            last_response_headers: Mutex::new(None),
+            app_credentials: None,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            etag_cache: None,
+            default_headers: Headers::new(),
+            min_age: DEFAULT_MIN_AGE,
+            language: None,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            circuit_breaker: None,
+            last_resends: Mutex::new(HashMap::new()),
+            retry_predicate: None,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
         }
     }
 
+    /// Creates a new Fractal Global Credits API client that sends the requests through the given
+    /// transport.
+    ///
+    /// This allows using a custom HTTP client, or returning canned responses in tests. The
+    /// connection pool and TLS settings of the client will have no effect on custom transports,
+    /// and clones of the client will share the same transport. An `Error::InvalidUrl` will be
+    /// returned if the URL is not valid.
+    pub fn with_transport<S: AsRef<str>, T: Transport + 'static>(url: S,
+                                                                 transport: T)
+                                                                 -> Result<Client> {
+        let mut client = Client::new_with_url(url)?;
+        client.transport = Some(Arc::new(transport));
+        Ok(client)
+    }
+
+    /// Creates a new Fractal Global Credits API client from the environment.
+    ///
+    /// The URL of the server will be read from the `FRACTAL_API_URL` environment variable, using
+    /// the [`DEFAULT_SERVER`](constant.DEFAULT_SERVER.html) if it's not set. If both the
+    /// `FRACTAL_APP_ID` and `FRACTAL_APP_SECRET` variables are set, they will be stored as the
+    /// application credentials of the client, that can be retrieved with
+    /// `get_app_credentials()`. An `Error::InvalidUrl` will be returned if the URL is not valid.
+    pub fn from_env() -> Result<Client> {
+        let mut client = match env::var(API_URL_ENV) {
+            Ok(url) => Client::new_with_url(url)?,
+            Err(_) => Client::build(DEFAULT_SERVER),
+        };
+        if let (Ok(id), Ok(secret)) = (env::var(APP_ID_ENV), env::var(APP_SECRET_ENV)) {
+            client.app_credentials = Some((id, secret));
+        }
+        Ok(client)
+    }
+
     /// Creates a new Fractal Global Credits API development client.
     pub fn new_dev() -> Client {
-        Client {
-            client: HyperClient::new(),
-            url: format!("{}v1/", FRACTAL_DEV_SERVER),
+        Client::build(FRACTAL_DEV_SERVER)
+    }
+
+    /// Sets the maximum number of idle connections kept alive in the connection pool.
+    ///
+    /// Keeping connections alive avoids a new TLS handshake in each request, which can be an
+    /// important overhead in services sending many requests. The default is
+    /// [`DEFAULT_MAX_IDLE_CONNECTIONS`](constant.DEFAULT_MAX_IDLE_CONNECTIONS.html). Note that this
+    /// creates a new connection pool, so current idle connections will be dropped.
+    pub fn set_max_idle_connections(&mut self, max_idle: usize) {
+        self.client = Client::build_hyper_client(max_idle, self.accept_invalid_certs);
+        self.max_idle_connections = max_idle;
+    }
+
+    /// Sets the maximum number of idle connections kept alive in the connection pool, returning
+    /// the client.
+    ///
+    /// This is the chainable version of `set_max_idle_connections()`, useful when creating the
+    /// client:
+    ///
+    /// ```
+    /// use fractal_api::Client;
+    ///
+    /// let client = Client::new().with_max_idle_connections(20);
+    /// ```
+    pub fn with_max_idle_connections(mut self, max_idle: usize) -> Client {
+        self.set_max_idle_connections(max_idle);
+        self
+    }
+
+    /// Gets the maximum number of idle connections kept alive in the connection pool.
+    pub fn get_max_idle_connections(&self) -> usize {
+        self.max_idle_connections
+    }
+
+    /// Sets whether invalid TLS certificates should be accepted.
+    ///
+    /// **This is dangerous**: it disables the verification of the certificate of the server, so
+    /// any server could impersonate the API. It is only meant to test the client against local
+    /// servers with self-signed certificates, and it's only available with the `insecure-tls`
+    /// feature, that should never be enabled in production.
+    #[cfg(feature = "insecure-tls")]
+    pub fn danger_accept_invalid_certs(&mut self, accept: bool) {
+        self.client = Client::build_hyper_client(self.max_idle_connections, accept);
+        self.accept_invalid_certs = accept;
+    }
+
+    /// Sets the maximum size of the responses, in bytes.
+    ///
+    /// Bigger responses will not be read, and an `Error::ResponseTooLarge` will be returned
+    /// instead, to avoid running out of memory with malicious or faulty responses. The default
+    /// is [`DEFAULT_MAX_RESPONSE_SIZE`](constant.DEFAULT_MAX_RESPONSE_SIZE.html).
+    pub fn set_max_response_size(&mut self, max_size: usize) {
+        self.max_response_size = max_size;
+    }
+
+    /// Gets the maximum size of the responses, in bytes.
+    pub fn get_max_response_size(&self) -> usize {
+        self.max_response_size
+    }
+
+    /// Sets whether the responses of frequently polled resources should be cached.
+    ///
+    /// If enabled, the last response of `get_me()` and `get_profile()` will be cached by URL and
+    /// access token, and its ETag will be sent to the server, so that it doesn't need to send the
+    /// response again if it didn't change. At most
+    /// [`ETAG_CACHE_SIZE`](constant.ETAG_CACHE_SIZE.html) responses are kept, evicting the least
+    /// recently used one. Caching is disabled by default, and disabling it clears the cache.
+    pub fn set_caching(&mut self, enabled: bool) {
+        if !enabled {
+            self.etag_cache = None;
+        } else if self.etag_cache.is_none() {
+            self.etag_cache = Some(Mutex::new(HashMap::new()));
+        }
+    }
+
+    /// Sets the headers that will be sent in every request.
+    ///
+    /// This can be used, for example, to add tracing headers to all the requests. The headers set
+    /// by each request, such as `Authorization` or `Accept`, will not be overridden.
+    pub fn set_default_headers(&mut self, headers: Headers) {
+        self.default_headers = headers;
+    }
+
+    /// Gets the headers that will be sent in every request.
+    pub fn get_default_headers(&self) -> &Headers {
+        &self.default_headers
+    }
+
+    /// Sets the minimum age of the users, in years.
+    ///
+    /// Birthdays of younger users will be rejected by `set_birthday()` before sending them to the
+    /// server. The default is [`DEFAULT_MIN_AGE`](constant.DEFAULT_MIN_AGE.html).
+    pub fn set_min_age(&mut self, min_age: u8) {
+        self.min_age = min_age;
+    }
+
+    /// Gets the minimum age of the users, in years.
+    pub fn get_min_age(&self) -> u8 {
+        self.min_age
+    }
+
+    /// Sets the preferred language of the messages sent by the server.
+    ///
+    /// The language will be sent in the `Accept-Language` header of every request, such as `es` or
+    /// `en-US`, so that the server can localize its messages where supported. By default, no
+    /// language is sent.
+    pub fn set_language(&mut self, lang: &str) {
+        self.language = Some(lang.to_owned());
+    }
+
+    /// Gets the preferred language of the messages sent by the server, if it was set.
+    pub fn get_language(&self) -> Option<&str> {
+        self.language.as_ref().map(|l| l.as_str())
+    }
+
+    /// Sets the maximum number of redirects followed in each request.
+    ///
+    /// If the server redirects a request more times, an `Error::TooManyRedirects` will be
+    /// returned. The `Authorization` header is removed when a redirect leads to a different
+    /// origin, so that the credentials never leak to another host. The default is
+    /// [`DEFAULT_MAX_REDIRECTS`](constant.DEFAULT_MAX_REDIRECTS.html), so redirects are not
+    /// followed.
+    pub fn set_max_redirects(&mut self, n: usize) {
+        self.max_redirects = n;
+    }
+
+    /// Gets the maximum number of redirects followed in each request.
+    pub fn get_max_redirects(&self) -> usize {
+        self.max_redirects
+    }
+
+    /// Sets the maximum number of attempts of the requests that can be retried.
+    ///
+    /// Only idempotent requests and requests with an idempotency key are retried after connection
+    /// errors, waiting [`RETRY_BACKOFF_MS`](constant.RETRY_BACKOFF_MS.html) milliseconds before
+    /// the first retry and doubling the wait before each following one. Once all the attempts
+    /// fail, the error of the last one is returned. The default is
+    /// [`DEFAULT_MAX_ATTEMPTS`](constant.DEFAULT_MAX_ATTEMPTS.html), and `0` or `1` disable the
+    /// retries.
+    pub fn set_max_attempts(&mut self, max_attempts: u32) {
+        self.max_attempts = max_attempts;
+    }
+
+    /// Gets the maximum number of attempts of the requests that can be retried.
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Enables the circuit breaker of the client.
+    ///
+    /// After `failure_threshold` consecutive failed requests, either because of connection errors
+    /// or server errors, the circuit will open and requests will fail fast with an
+    /// `Error::CircuitOpen` for the given `cooldown`. After it, a single request will be sent to
+    /// probe the server, closing the circuit if it succeeds or opening it again if it fails.
+    /// Connection errors will not be retried while the circuit is open. Clones of the client get
+    /// their own closed circuit breaker with the same configuration.
+    pub fn enable_circuit_breaker(&mut self, failure_threshold: u32, cooldown: Duration) {
+        self.circuit_breaker = Some(CircuitBreaker::new(failure_threshold, cooldown));
+    }
+
+    /// Sets the predicate that decides if a failed request should be retried.
+    ///
+    /// The predicate receives the error of the request and the number of attempts made so far,
+    /// starting at 1, and returns whether the request should be sent again. By default, only
+    /// idempotent requests and requests with an idempotency key are retried, up to the maximum
+    /// number of attempts set with `set_max_attempts()`. The predicate can only narrow this, so
+    /// requests that are not safe to repeat, such as `new_transaction()`, are never retried
+    /// regardless of it. The circuit breaker, if enabled, also stops the retries. Clones of the
+    /// client share the same predicate.
+    ///
+    /// ```
+    /// use fractal_api::Client;
+    /// use fractal_api::error::Error;
+    ///
+    /// let mut client = Client::new();
+    /// client.set_retry_predicate(Box::new(|_: &Error, attempt: u32| attempt < 3));
+    /// ```
+    pub fn set_retry_predicate(&mut self, f: Box<Fn(&Error, u32) -> bool + Send + Sync>) {
+        self.retry_predicate = Some(Arc::from(f));
+    }
+
+    /// Gets the application ID and secret of the client, if they were set in the environment.
+    ///
+    /// They can be used to get an application token with the `token()` method.
+    pub fn get_app_credentials(&self) -> Option<(&str, &str)> {
+        match self.app_credentials {
+            Some((ref id, ref secret)) => Some((id.as_str(), secret.as_str())),
+            None => None,
+        }
+    }
+
+    /// Gets the headers of the last response received by the client, if any.
+    ///
+    /// This is useful for debugging, for example to check rate limit headers. Note that if the
+    /// client is being used from multiple threads, the headers might belong to a response to
+    /// another thread.
+    pub fn last_response_headers(&self) -> Option<Headers> {
+        match self.last_response_headers.lock() {
+            Ok(headers) => headers.clone(),
+            Err(_) => None,
+        }
+    }
+
+    /// Gets the ID of the last request sent by the client, if the server sent it.
+    ///
+    /// It's taken from the [`REQUEST_ID_HEADER`](constant.REQUEST_ID_HEADER.html) header, and can
+    /// be used to correlate client side failures with the server logs.
+    pub fn last_request_id(&self) -> Option<String> {
+        self.last_response_headers()
+            .and_then(|headers| {
+                headers.get_raw(REQUEST_ID_HEADER)
+                    .and_then(|values| values.first())
+                    .and_then(|value| String::from_utf8(value.clone()).ok())
+            })
+    }
+
+    /// Builds the underlying HTTP client with the given configuration.
+    ///
+    /// Invalid certificates can only be accepted with the `insecure-tls` feature, so the flag is
+    /// ignored without it.
+    #[cfg_attr(not(feature = "insecure-tls"), allow(unused_variables))]
+    fn build_hyper_client(max_idle: usize, accept_invalid_certs: bool) -> HyperClient {
+        let mut client = HyperClient::with_pool_config(PoolConfig { max_idle: max_idle });
+        // Redirects are followed by the client itself, to control where the credentials are sent.
+        client.set_redirect_policy(RedirectPolicy::FollowNone);
+        #[cfg(feature = "insecure-tls")]
+        {
+            if accept_invalid_certs {
+                Client::disable_cert_verification(&mut client);
+            }
         }
+        client
+    }
+
+    /// Disables the verification of TLS certificates in the given HTTP client.
+    #[cfg(feature = "insecure-tls")]
+    fn disable_cert_verification(client: &mut HyperClient) {
+        client.set_ssl_verifier(Box::new(|ctx: &mut SslContext| {
+            ctx.set_verify(SSL_VERIFY_NONE, None)
+        }));
     }
 
     // /// Sets the read timeout for requests.
@@ -33,11 +357,47 @@ impl Client {
     // }
 }
 
+/// Creates a client for the [`DEFAULT_SERVER`](constant.DEFAULT_SERVER.html), like `Client::new()`.
+///
+/// This allows selecting the server at compile time with the `dev` feature, so that the same code
+/// can be built for the development and production environments.
 impl Default for Client {
     fn default() -> Client {
+        Client::new()
+    }
+}
+
+/// Clones the client configuration.
+///
+/// The underlying HTTP client can't be shared, so the new client will create its own connections
+/// to the same API URL. This makes it cheap to give each worker thread its own client, instead of
+/// sharing one behind a lock.
+impl Clone for Client {
+    fn clone(&self) -> Client {
         Client {
-            client: HyperClient::new(),
-            url: format!("{}v1/", FRACTAL_SERVER),
+            client: Client::build_hyper_client(self.max_idle_connections,
+                                               self.accept_invalid_certs),
+            transport: self.transport.clone(),
+            url: self.url.clone(),
+            max_idle_connections: self.max_idle_connections,
+            accept_invalid_certs: self.accept_invalid_certs,
+            last_response_headers: Mutex::new(None),
+            app_credentials: self.app_credentials.clone(),
+            max_response_size: self.max_response_size,
+            etag_cache: match self.etag_cache {
+                Some(_) => Some(Mutex::new(HashMap::new())),
+                None => None,
+            },
+            default_headers: self.default_headers.clone(),
+            min_age: self.min_age,
+            language: self.language.clone(),
+            max_redirects: self.max_redirects,
+            circuit_breaker: self.circuit_breaker.as_ref().map(|b| {
+                CircuitBreaker::new(b.get_failure_threshold(), b.get_cooldown())
+            }),
+            last_resends: Mutex::new(HashMap::new()),
+            retry_predicate: self.retry_predicate.clone(),
+            max_attempts: self.max_attempts,
         }
     }
 }
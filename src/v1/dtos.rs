@@ -0,0 +1,359 @@
+//! Data transfer objects not yet provided by the `fractal_dto` crate.
+//!
+//! These follow the same conventions as the ones in `fractal_dto`, and should be moved there once
+//! they are stabilized.
+
+use chrono::{DateTime, UTC};
+use rustc_serialize::json::Json;
+use utils::{WalletAddress, Amount};
+use dto::{DTO, RelationshipDTO, FriendRequestDTO};
+
+/// Authenticator enrollment information DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct AuthenticatorEnrollmentDTO {
+    /// Base32 encoded secret of the authenticator.
+    pub secret: String,
+    /// `otpauth://` URL of the authenticator.
+    pub otpauth_url: String,
+}
+
+impl DTO for AuthenticatorEnrollmentDTO {}
+
+/// Mailing list subscription DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct SubscribeEmailDTO {
+    /// Email to subscribe.
+    pub email: String,
+    /// Name of the subscriber.
+    pub name: String,
+}
+
+impl DTO for SubscribeEmailDTO {}
+
+/// Webhook envelope DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct WebhookDTO {
+    /// Type of the event.
+    pub event: String,
+    /// Data of the event, dependent on the event type.
+    pub data: Json,
+}
+
+impl DTO for WebhookDTO {}
+
+/// Verification status webhook DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct VerificationWebhookDTO {
+    /// ID of the user.
+    pub user_id: u64,
+    /// New verification status of the user.
+    pub status: String,
+}
+
+impl DTO for VerificationWebhookDTO {}
+
+/// Server information DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct ServerInfoDTO {
+    /// Version of the server build.
+    pub version: String,
+    /// Version of the API served.
+    pub api_version: String,
+}
+
+impl DTO for ServerInfoDTO {}
+
+/// Transaction fee estimation request DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct EstimateFeeDTO {
+    /// Amount of the transaction.
+    pub amount: Amount,
+}
+
+impl DTO for EstimateFeeDTO {}
+
+/// Transaction fee DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct FeeDTO {
+    /// Fee of the transaction.
+    pub fee: Amount,
+}
+
+impl DTO for FeeDTO {}
+
+/// Internal transfer DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct InternalTransferDTO {
+    /// Kind of the origin wallet.
+    pub from: String,
+    /// Kind of the destination wallet.
+    pub to: String,
+    /// Amount to transfer.
+    pub amount: Amount,
+}
+
+impl DTO for InternalTransferDTO {}
+
+/// User field clearing DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct ClearUserFieldsDTO {
+    /// Names of the fields to clear.
+    pub fields: Vec<String>,
+}
+
+impl DTO for ClearUserFieldsDTO {}
+
+/// Uploaded image DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct ImageDTO {
+    /// URL of the image.
+    pub image_url: String,
+}
+
+impl DTO for ImageDTO {}
+
+/// Trust score sample DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct TrustScoreSampleDTO {
+    /// Time of the sample.
+    pub timestamp: DateTime<UTC>,
+    /// Trust score of the user at the time of the sample.
+    pub trust_score: i8,
+}
+
+impl DTO for TrustScoreSampleDTO {}
+
+/// Filtered user search DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct FilteredSearchUserDTO {
+    /// String to search in names, emails etc.
+    pub random: Option<String>,
+    /// Username to search.
+    pub username: Option<String>,
+    /// Email to search.
+    pub email: Option<String>,
+    /// Minimum sybil score of the users.
+    pub min_sybil_score: Option<i8>,
+    /// Maximum sybil score of the users.
+    pub max_sybil_score: Option<i8>,
+    /// Minimum trust score of the users.
+    pub min_trust_score: Option<i8>,
+    /// Whether to include the current user in the results.
+    pub include_me: bool,
+    /// Whether to include the friends of the current user in the results.
+    pub include_friends: bool,
+}
+
+impl DTO for FilteredSearchUserDTO {}
+
+/// Relationship update DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct UpdateRelationshipDTO {
+    /// New relationship of the connection.
+    pub relationship: RelationshipDTO,
+}
+
+impl DTO for UpdateRelationshipDTO {}
+
+/// Wallet DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct WalletDTO {
+    /// Address of the wallet.
+    pub address: WalletAddress,
+    /// Kind of the wallet, `checking` or `cold`.
+    pub kind: String,
+    /// Label given to the wallet by the user.
+    pub label: Option<String>,
+    /// Balance of the wallet.
+    pub balance: Amount,
+}
+
+impl DTO for WalletDTO {}
+
+/// Wallet address DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct WalletAddressDTO {
+    /// The wallet address.
+    pub address: WalletAddress,
+}
+
+impl DTO for WalletAddressDTO {}
+
+/// Error response DTO.
+///
+/// Like the `ResponseDTO`, but with the machine readable error code sent by the server in some
+/// error responses.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct ErrorResponseDTO {
+    /// Human readable message of the error.
+    pub message: String,
+    /// Machine readable code of the error.
+    pub code: Option<String>,
+}
+
+impl DTO for ErrorResponseDTO {}
+
+/// Reward DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct RewardDTO {
+    /// Amount of the reward.
+    pub amount: Amount,
+    /// Time when the reward will be given.
+    pub timestamp: DateTime<UTC>,
+}
+
+impl DTO for RewardDTO {}
+
+/// Friend request batch DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct FriendRequestBatchDTO {
+    /// Friend requests to send.
+    pub requests: Vec<FriendRequestDTO>,
+}
+
+impl DTO for FriendRequestBatchDTO {}
+
+/// Batch operation result DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct BatchResultDTO {
+    /// Whether the operation succeeded.
+    pub success: bool,
+    /// Message of the server for the operation.
+    pub message: String,
+}
+
+impl DTO for BatchResultDTO {}
+
+/// Account deletion DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct DeleteAccountDTO {
+    /// Current password of the user.
+    pub password: String,
+}
+
+impl DTO for DeleteAccountDTO {}
+
+/// Pending transaction information DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct PendingTransactionInfoDTO {
+    /// Code of the pending transaction.
+    pub code: String,
+    /// ID of the user sending the transaction.
+    pub origin_id: u64,
+    /// ID of the user receiving the transaction.
+    pub destination_id: u64,
+    /// Wallet address receiving the transaction.
+    pub destination_address: WalletAddress,
+    /// Amount of the transaction.
+    pub amount: Amount,
+    /// Time when the transaction was created.
+    pub timestamp: DateTime<UTC>,
+}
+
+impl DTO for PendingTransactionInfoDTO {}
+
+/// User balances DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct BalancesDTO {
+    /// Checking wallet balance.
+    pub checking: Amount,
+    /// Cold wallet balance.
+    pub cold: Amount,
+    /// Pending balance.
+    pub pending: Amount,
+}
+
+impl DTO for BalancesDTO {}
+
+/// Count DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct CountDTO {
+    /// Number of elements.
+    pub count: u64,
+}
+
+impl DTO for CountDTO {}
+
+/// Notification DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct NotificationDTO {
+    /// ID of the notification.
+    pub id: u64,
+    /// Kind of the notification: `friend_request`, `transaction` or `verification`.
+    pub kind: String,
+    /// Time when the notification was generated.
+    pub timestamp: DateTime<UTC>,
+    /// Whether the notification has been read.
+    pub read: bool,
+    /// ID of the friend request, for friend request notifications.
+    pub connection_id: Option<u64>,
+    /// ID of the user that originated the event, if any.
+    pub user_id: Option<u64>,
+    /// ID of the transaction, for transaction notifications.
+    pub transaction_id: Option<u64>,
+    /// Amount of the transaction, for transaction notifications.
+    pub amount: Option<Amount>,
+    /// New verification status, for verification notifications.
+    pub status: Option<String>,
+}
+
+impl DTO for NotificationDTO {}
+
+/// Bulk user enabling DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct BulkSetEnabledDTO {
+    /// IDs of the users to update.
+    pub user_ids: Vec<u64>,
+    /// Whether the users should be enabled.
+    pub enabled: bool,
+}
+
+impl DTO for BulkSetEnabledDTO {}
+
+/// Platform statistics DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct PlatformStatsDTO {
+    /// Total number of users.
+    pub total_users: u64,
+    /// Total number of transactions.
+    pub total_transactions: u64,
+    /// Total amount transferred in all the transactions.
+    pub total_volume: Amount,
+    /// Number of users active in the last day.
+    pub daily_active_users: u64,
+}
+
+impl DTO for PlatformStatsDTO {}
+
+/// Notification preferences DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct NotificationPrefsDTO {
+    /// Whether to notify about transactions.
+    pub transactions: bool,
+    /// Whether to notify about friend requests.
+    pub friend_requests: bool,
+    /// Whether to send marketing notifications.
+    pub marketing: bool,
+}
+
+impl DTO for NotificationPrefsDTO {}
+
+/// Wallet address batch DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct WalletAddressBatchDTO {
+    /// Wallet addresses to resolve.
+    pub addresses: Vec<String>,
+}
+
+impl DTO for WalletAddressBatchDTO {}
+
+/// Wallet address owner DTO.
+#[derive(Debug, Clone, RustcEncodable, RustcDecodable)]
+pub struct WalletAddressOwnerDTO {
+    /// Wallet address.
+    pub address: String,
+    /// ID of the user owning the address, if it's a known address.
+    pub user_id: Option<u64>,
+}
+
+impl DTO for WalletAddressOwnerDTO {}
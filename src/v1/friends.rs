@@ -1,18 +1,14 @@
-use std::io::Read;
-
 use hyper::method::Method;
 use hyper::header::{Headers, Authorization};
-use rustc_serialize::json;
 
-use dto::{ResponseDTO,FromDTO, PendingFriendRequestDTO, FriendRequestDTO, ConfirmFriendRequestDTO, ProfileDTO,
-          RelationshipDTO as Relationship};
-use hyper::status::StatusCode;
+use dto::{FromDTO, ResponseDTO, PendingFriendRequestDTO, FriendRequestDTO, ConfirmFriendRequestDTO,
+          ProfileDTO, RelationshipDTO as Relationship};
 use error::{Result, Error};
 use super::{Client, VoidDTO};
-use super::types::{PendingFriendRequest, Profile};
+use super::dtos::{UpdateRelationshipDTO, FriendRequestBatchDTO, BatchResultDTO, CountDTO};
+use super::types::{PendingFriendRequest, Profile, ApiMessage};
 use super::oauth::AccessToken;
 
-
 /// Methods for working with friend requests.
 impl Client {
     /// Creates a pending invitation to connect to the user
@@ -21,75 +17,101 @@ impl Client {
                                                 user: u64,
                                                 relation: Relationship,
                                                 message: Option<M>)
-                                                -> Result<(ResponseDTO)> {
+                                                -> Result<ApiMessage> {
+        let user_id = access_token.get_user_id();
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = FriendRequestDTO {
+            origin_id: user_id.unwrap(),
+            destination_id: user,
+            relationship: relation,
+            message: message.and_then(|mess| Some(mess.into())),
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}create_friend_request", self.url),
+                          headers,
+                          Some(&dto))?;
+        self.decode_message(&mut response)
+    }
+
+    /// Creates pending invitations to connect to the given users, in a single request
+    ///
+    /// Each request is given as the ID of the user, the relationship and an optional message. The
+    /// result of each request is returned in the same order, so that the failure of some of them
+    /// doesn't fail the whole batch. If the server doesn't return a result for each request, an
+    /// `Error::Server` is returned, since the results can't be matched with the requests.
+    pub fn send_friend_requests(&self,
+                                access_token: &AccessToken,
+                                requests: &[(u64, Relationship, Option<String>)])
+                                -> Result<Vec<Result<ApiMessage>>> {
         let user_id = access_token.get_user_id();
-        if user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = FriendRequestDTO {
-                origin_id: user_id.unwrap(),
-                destination_id: user,
-                relationship: relation,
-                message: message.and_then(|mess| Some(mess.into())),
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}create_friend_request", self.url),
-                              headers,
-                              Some(&dto))?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let res: ResponseDTO = json::decode(&response_str)?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(res)
-                }
-                StatusCode::Accepted => {
-                    Ok(res)
-                }
-                _ => {
-                    Err(Error::Forbidden(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = FriendRequestBatchDTO {
+            requests: requests.iter()
+                .map(|&(user, ref relation, ref message)| {
+                    FriendRequestDTO {
+                        origin_id: user_id.unwrap(),
+                        destination_id: user,
+                        relationship: relation.clone(),
+                        message: message.clone(),
+                    }
+                })
+                .collect(),
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}create_friend_requests", self.url),
+                          headers,
+                          Some(&dto))?;
+        let results: Vec<BatchResultDTO> = self.decode_response(&mut response)?;
+        if results.len() != requests.len() {
+            return Err(Error::Server {
+                code: None,
+                message: String::from("the server did not return a result for each friend request"),
+            });
         }
+        Ok(results.into_iter()
+            .map(|r| if r.success {
+                Ok(ApiMessage::from_dto(ResponseDTO { message: r.message })?)
+            } else {
+                Err(Error::BadRequest(r.message))
+            })
+            .collect())
     }
 
     /// Confirms a connection
+    ///
+    /// The `user` is the ID of the user that sent the friend request, while the authenticated user
+    /// must be its destination. Since a user can't send a friend request to themselves, an
+    /// `Error::BadRequest` is returned without contacting the server if both IDs are the same.
     pub fn confirm_friend_request(&self,
                                   access_token: &AccessToken,
                                   request_id: u64,
                                   user: u64)
-                                  -> Result<(ResponseDTO)> {
-        let user_id = access_token.get_user_id();
-        if user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = ConfirmFriendRequestDTO {
-                request_id: request_id,
-                origin: user,
-                destination: user_id.unwrap(),
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}confirm_friend_request",
-                                   self.url,
-                                  ),
-                              headers,
-                              Some(&dto))?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            match response.status {
-                StatusCode::Ok => {
-                    let res: ResponseDTO = json::decode(&response_str)?;
-                    Ok(res)
-                }
-                _ => {
-                    Err(Error::Forbidden(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
+                                  -> Result<ApiMessage> {
+        self.require_user(access_token)?;
+        let user_id = access_token.get_user_id().unwrap();
+        if user == user_id {
+            return Err(Error::BadRequest(String::from("the origin of the friend request must be \
+                                                       a different user than the one confirming \
+                                                       it")));
         }
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = ConfirmFriendRequestDTO {
+            request_id: request_id,
+            origin: user,
+            destination: user_id,
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}confirm_friend_request",
+                               self.url,
+                              ),
+                          headers,
+                          Some(&dto))?;
+        self.decode_message(&mut response)
     }
 
     /// Gets all the pending friend requests for the given user.
@@ -97,94 +119,103 @@ impl Client {
                                access_token: &AccessToken,
                                user_id: u64)
                                -> Result<Vec<PendingFriendRequest>> {
-        if (access_token.is_admin() || access_token.is_user(user_id)) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}friend_requests/{}", self.url, user_id),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let connections: Vec<PendingFriendRequestDTO> = json::decode(&response_str)?;
-            Ok(connections.into_iter()
-                .map(|t| PendingFriendRequest::from_dto(t).unwrap())
-                .collect())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user or admin \
-                                               token, and in the case of an user token, the ID \
-                                               in the token must be the same as the given ID")))
-        }
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}friend_requests/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<PendingFriendRequestDTO, PendingFriendRequest>(&mut response)
+    }
+
+    /// Gets all the pending friend requests sent by the given user.
+    ///
+    /// Requests that have been accepted will appear in `get_friends()` instead, and requests that
+    /// have been rejected will no longer be returned.
+    pub fn get_sent_friend_requests(&self,
+                                    access_token: &AccessToken,
+                                    user_id: u64)
+                                    -> Result<Vec<PendingFriendRequest>> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}sent_friend_requests/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<PendingFriendRequestDTO, PendingFriendRequest>(&mut response)
     }
 
     /// Returns the given users friends
     pub fn get_friends(&self, access_token: &AccessToken, user_id: u64) -> Result<Vec<Profile>> {
-        if (access_token.is_admin() || access_token.is_user(user_id)) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}friends/{}", self.url, user_id),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let friends: Vec<ProfileDTO> = json::decode(&response_str)?;
-            Ok(friends.into_iter()
-                .map(|f| Profile::from_dto(f).unwrap())
-                .collect())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user or admin \
-                                               token, and in the case of an user token, the ID \
-                                               in the token must be the same as the given ID")))
-        }
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}friends/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<ProfileDTO, Profile>(&mut response)
+    }
+
+    /// Returns the number of friends of the given user
+    ///
+    /// This is much lighter than `get_friends()` when only the number of friends is needed.
+    pub fn get_friend_count(&self, access_token: &AccessToken, user_id: u64) -> Result<u64> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}friend_count/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(self.decode_response::<CountDTO>(&mut response)?.count)
     }
 
     /// Rejects the friend request for the given user
     pub fn reject_friend_request(&self, access_token: &AccessToken, request_id: u64) 
-                                -> Result<(ResponseDTO)> {
+                                -> Result<ApiMessage> {
 
-        if access_token.get_user_id().is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Post,
-                              format!("{}reject_friend_request/{}", self.url, request_id),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            match response.status {
-                StatusCode::Ok => {
-                    let res: ResponseDTO = json::decode(&response_str)?;
-                    Ok(res)
-                }
-                _ => {
-                    Err(Error::Forbidden(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user or admin \
-                                               token, and in the case of an user token, the ID \
-                                               in the token must be the same as the given ID")))
-        }
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Post,
+                          format!("{}reject_friend_request/{}", self.url, request_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_message(&mut response)
     }
     
+    /// Updates the relationship of an existing connection
+    ///
+    /// The user of the token must be part of the connection.
+    pub fn update_relationship(&self,
+                               access_token: &AccessToken,
+                               connection_id: u64,
+                               relation: Relationship)
+                               -> Result<()> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateRelationshipDTO { relationship: relation };
+        let _ = self.send_request(Method::Post,
+                          format!("{}update_relationship/{}", self.url, connection_id),
+                          headers,
+                          Some(&dto))?;
+        Ok(())
+    }
+
     /// Unfriends the given user
     pub fn unfriend(&self, access_token: &AccessToken, request_id: u64) -> Result<()> {
 
-        if access_token.get_user_id().is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let _ = self.send_request(Method::Delete,
-                              format!("{}friend/{}", self.url, request_id),
-                              headers,
-                              None::<&VoidDTO>)?;
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user or admin \
-                                               token, and in the case of an user token, the ID \
-                                               in the token must be the same as the given ID")))
-        }
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let _ = self.send_request(Method::Delete,
+                          format!("{}friend/{}", self.url, request_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(())
     }
 }
@@ -1,38 +1,140 @@
 //! First version of the Fractal Global Credits API.
 
-use std::io::Read;
+use std::io::{self, Read};
+use std::{cmp, str, thread};
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use hyper::Client as HyperClient;
 use hyper::header::{Headers, Accept, qitem};
 use hyper::status::StatusCode;
 use hyper::mime::{Mime, TopLevel, SubLevel, Attr, Value};
 use hyper::method::Method;
-use hyper::client::response::Response;
 
-use rustc_serialize::json;
-use dto::{DTO, ResponseDTO};
+use chrono::{NaiveDate, Datelike};
+use rustc_serialize::{json, Decodable};
+use dto::{DTO, FromDTO, ResponseDTO};
 
 /// Fractal API server.
 pub const FRACTAL_SERVER: &'static str = "https://api.fractal.global/";
 /// Fractal development API server.
 pub const FRACTAL_DEV_SERVER: &'static str = "https://dev.fractal.global/";
+/// Default API server, used by `Client::default()`.
+///
+/// It will be the development server if the `dev` feature is enabled, and the production server
+/// otherwise.
+#[cfg(not(feature = "dev"))]
+pub const DEFAULT_SERVER: &'static str = FRACTAL_SERVER;
+/// Default API server, used by `Client::default()`.
+///
+/// It will be the development server if the `dev` feature is enabled, and the production server
+/// otherwise.
+#[cfg(feature = "dev")]
+pub const DEFAULT_SERVER: &'static str = FRACTAL_DEV_SERVER;
+/// Header used to send idempotency keys.
+///
+/// Requests with non-idempotent methods, such as `POST`, are only retried on connection errors if
+/// they carry this header, so that the server can detect the duplicated request.
+pub const IDEMPOTENCY_KEY_HEADER: &'static str = "Idempotency-Key";
+/// Header containing the ID of the request, as assigned by the server.
+pub const REQUEST_ID_HEADER: &'static str = "X-Request-Id";
+/// Default maximum size of the responses, in bytes.
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 4 * 1024 * 1024;
+/// Maximum number of responses kept in the ETag cache of the client.
+///
+/// Once it's full, the least recently used response is evicted.
+pub const ETAG_CACHE_SIZE: usize = 128;
+/// Default minimum age of the users, in years.
+pub const DEFAULT_MIN_AGE: u8 = 18;
+/// Maximum plausible age of the users, in years.
+pub const MAX_AGE: u8 = 150;
+/// Environment variable with the URL of the API server, used by `Client::from_env()`.
+pub const API_URL_ENV: &'static str = "FRACTAL_API_URL";
+/// Environment variable with the application ID, used by `Client::from_env()`.
+pub const APP_ID_ENV: &'static str = "FRACTAL_APP_ID";
+/// Environment variable with the application secret, used by `Client::from_env()`.
+pub const APP_SECRET_ENV: &'static str = "FRACTAL_APP_SECRET";
+/// Default maximum number of idle connections kept alive by the client.
+pub const DEFAULT_MAX_IDLE_CONNECTIONS: usize = 5;
+/// Default maximum number of redirects followed by the client in each request.
+pub const DEFAULT_MAX_REDIRECTS: usize = 0;
+/// Default maximum number of attempts of a request that can be retried, including the first one.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// Time to wait before the first retry of a request, in milliseconds.
+///
+/// The wait is doubled before each following retry.
+pub const RETRY_BACKOFF_MS: u64 = 100;
+/// Minimum time between resends of the same confirmation for the same user, in seconds.
+pub const RESEND_COOLDOWN_SECS: u64 = 60;
 
 pub mod types;
+mod dtos;
 mod client;
 pub mod oauth;
+pub mod webhook;
+pub mod qr;
+pub mod transport;
 mod public;
 mod user;
 mod friends;
 mod transaction;
+mod notifications;
+mod raw;
+mod circuit;
+
+pub use self::transaction::{TransactionIter, TRANSACTION_PAGE_SIZE};
+pub use self::user::{UserSearch, USER_PAGE_SIZE};
 
 use error::{Result, Error};
+use self::dtos::ErrorResponseDTO;
+use self::oauth::AccessToken;
+use self::transport::{Transport, RawResponse};
+use self::circuit::CircuitBreaker;
+use self::types::ApiMessage;
 
 /// The client struct.
 ///
 /// This struct will be in charge of connections to the Fractal Global Credits API.
+///
+/// The client is `Send` and `Sync`, so a single client can be shared between threads, for example
+/// behind an `Arc`, and used concurrently. Each request takes its own connection from the
+/// connection pool. The only shared mutable state is the record of the last response headers,
+/// that is protected by a lock, so with concurrent requests `last_response_headers()` might return
+/// the headers of any of them. Cloning the client is also cheap, if each thread should have its
+/// own connection pool.
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use fractal_api::Client;
+///
+/// fn assert_send_sync<T: Send + Sync>() {}
+/// assert_send_sync::<Client>();
+///
+/// let client = Arc::new(Client::new());
+/// let thread_client = client.clone();
+/// let handle = thread::spawn(move || thread_client.get_max_idle_connections());
+/// assert_eq!(handle.join().unwrap(), client.get_max_idle_connections());
+/// ```
 pub struct Client {
     client: HyperClient,
+    transport: Option<Arc<Transport>>,
     url: String,
+    max_idle_connections: usize,
+    accept_invalid_certs: bool,
+    last_response_headers: Mutex<Option<Headers>>,
+    app_credentials: Option<(String, String)>,
+    max_response_size: usize,
+    etag_cache: Option<Mutex<HashMap<String, (String, String, Instant)>>>,
+    default_headers: Headers,
+    min_age: u8,
+    language: Option<String>,
+    max_redirects: usize,
+    circuit_breaker: Option<CircuitBreaker>,
+    last_resends: Mutex<HashMap<String, Instant>>,
+    retry_predicate: Option<Arc<Fn(&Error, u32) -> bool + Send + Sync>>,
+    max_attempts: u32,
 }
 
 #[derive(RustcDecodable, RustcEncodable)]
@@ -40,75 +142,455 @@ struct VoidDTO;
 impl DTO for VoidDTO {}
 
 impl Client {
+    /// Reads the body of the response, up to the maximum response size of the client.
+    ///
+    /// The size is checked before decoding the body as UTF-8, so that a character cut at the limit
+    /// is not reported as an invalid body.
+    fn read_body(&self, response: &mut RawResponse) -> Result<String> {
+        let mut body = Vec::new();
+        let limit = self.max_response_size as u64;
+        let read = response.take(limit.saturating_add(1)).read_to_end(&mut body)?;
+        if read as u64 > limit {
+            return Err(Error::ResponseTooLarge);
+        }
+        String::from_utf8(body)
+            .map_err(|e| Error::IO(io::Error::new(io::ErrorKind::InvalidData, e)))
+    }
+
+    /// Reads the body of the response and decodes it from JSON.
+    fn decode_response<T: Decodable>(&self, response: &mut RawResponse) -> Result<T> {
+        let response_str = self.read_body(response)?;
+        Ok(json::decode(&response_str)?)
+    }
+
+    /// Reads the body of the response and decodes it from JSON, if there is any.
+    ///
+    /// Some endpoints answer a successful operation with an empty body, in which case `None` is
+    /// returned instead of a decoding error.
+    fn decode_optional_response<T: Decodable>(&self,
+                                              response: &mut RawResponse)
+                                              -> Result<Option<T>> {
+        let response_str = self.read_body(response)?;
+        if response_str.trim().is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(json::decode(&response_str)?))
+        }
+    }
+
+    /// Reads the message sent by the server, using an empty message if the body is empty.
+    fn decode_message(&self, response: &mut RawResponse) -> Result<ApiMessage> {
+        match self.decode_optional_response::<ResponseDTO>(response)? {
+            Some(dto) => Ok(ApiMessage::from_dto(dto)?),
+            None => Ok(ApiMessage::default()),
+        }
+    }
+
+    /// Reads the body of the response, decodes it into the given DTO and converts it.
+    fn decode_dto<D: Decodable, T: FromDTO<D>>(&self, response: &mut RawResponse) -> Result<T> {
+        let dto: D = self.decode_response(response)?;
+        Ok(T::from_dto(dto)?)
+    }
+
+    /// Decodes the given response body into the given DTO and converts it.
+    fn decode_dto_body<D: Decodable, T: FromDTO<D>>(&self, body: &str) -> Result<T> {
+        let dto: D = json::decode(body)?;
+        Ok(T::from_dto(dto)?)
+    }
+
+    /// Reads the body of the response, decodes it into a list of the given DTO and converts it.
+    fn decode_dto_list<D: Decodable, T: FromDTO<D>>(&self,
+                                                    response: &mut RawResponse)
+                                                    -> Result<Vec<T>> {
+        let dtos: Vec<D> = self.decode_response(response)?;
+        let mut list = Vec::with_capacity(dtos.len());
+        for dto in dtos {
+            list.push(T::from_dto(dto)?);
+        }
+        Ok(list)
+    }
+
+    /// Checks that the given token is an unexpired public token.
+    fn require_public(&self, access_token: &AccessToken) -> Result<()> {
+        if access_token.is_public() && !access_token.has_expired() {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
+        }
+    }
+
+    /// Checks that the given token is an unexpired user token.
+    fn require_user(&self, access_token: &AccessToken) -> Result<()> {
+        if access_token.get_user_id().is_some() && !access_token.has_expired() {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
+        }
+    }
+
+    /// Checks that the given token is an unexpired public or user token.
+    fn require_public_or_user(&self, access_token: &AccessToken) -> Result<()> {
+        if (access_token.is_public() || access_token.get_user_id().is_some()) &&
+           !access_token.has_expired() {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(String::from("the token must be an unexpired public or user \
+                                               token")))
+        }
+    }
+
+    /// Checks that the given token is an unexpired admin token.
+    fn require_admin(&self, access_token: &AccessToken) -> Result<()> {
+        if access_token.is_admin() && !access_token.has_expired() {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(String::from("the token must be an unexpired admin token")))
+        }
+    }
+
+    /// Checks that the given token is an unexpired admin token, or an unexpired user token for
+    /// the given user.
+    fn require_user_or_admin(&self, access_token: &AccessToken, user_id: u64) -> Result<()> {
+        if (access_token.is_user(user_id) || access_token.is_admin()) &&
+           !access_token.has_expired() {
+            Ok(())
+        } else {
+            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
+                                               token, and in the case of a user token, the ID \
+                                               in the token must match the given ID")))
+        }
+    }
+
+    /// Checks that the given operation was not successfully performed for the user of the token
+    /// in the last `RESEND_COOLDOWN_SECS` seconds.
+    ///
+    /// This avoids flooding the server with resends when the user repeatedly asks for them.
+    fn check_resend(&self, operation: &str, access_token: &AccessToken) -> Result<()> {
+        let cooldown = Duration::from_secs(RESEND_COOLDOWN_SECS);
+        if let (Some(user_id), Ok(last_resends)) = (access_token.get_user_id(),
+                                                    self.last_resends.lock()) {
+            if let Some(last) = last_resends.get(&format!("{}/{}", operation, user_id)) {
+                let elapsed = last.elapsed();
+                if elapsed < cooldown {
+                    return Err(Error::RateLimited(cooldown - elapsed));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records that the given operation was successfully performed for the user of the token.
+    ///
+    /// It must only be called once the request succeeds, so that failed resends can be retried
+    /// right away.
+    fn record_resend(&self, operation: &str, access_token: &AccessToken) {
+        let cooldown = Duration::from_secs(RESEND_COOLDOWN_SECS);
+        if let (Some(user_id), Ok(mut last_resends)) = (access_token.get_user_id(),
+                                                        self.last_resends.lock()) {
+            last_resends.retain(|_, last| last.elapsed() < cooldown);
+            let _ = last_resends.insert(format!("{}/{}", operation, user_id), Instant::now());
+        }
+    }
+
+    /// Records the headers of the given response, and its outcome in the circuit breaker.
+    ///
+    /// Server errors count as failures, and any other response as a success.
+    fn record_response(&self, response: &RawResponse) {
+        if let Ok(mut last_headers) = self.last_response_headers.lock() {
+            *last_headers = Some(response.headers.clone());
+        }
+        if let Some(ref breaker) = self.circuit_breaker {
+            if response.status.to_u16() >= 500 {
+                breaker.record_failure();
+            } else {
+                breaker.record_success();
+            }
+        }
+    }
+
+    /// Sends a `GET` request and reads the body of the response, using the ETag cache if enabled.
+    ///
+    /// If there is a cached response for the URL and credentials, its ETag is sent in the
+    /// `If-None-Match` header, and the cached body is returned if the server responds that it was
+    /// not modified. Responses are cached per `Authorization` header, so that clients shared
+    /// between users never return the response cached for another user. When the cache is full,
+    /// the least recently used response is evicted.
+    fn send_cached_get(&self, url: String, headers: Headers) -> Result<String> {
+        let cache = match self.etag_cache {
+            Some(ref cache) => cache,
+            None => {
+                let mut response = self.send_request(Method::Get, url, headers, None::<&VoidDTO>)?;
+                return self.read_body(&mut response);
+            }
+        };
+        let key = match headers.get_raw("Authorization").and_then(|values| values.first()) {
+            Some(authorization) => format!("{} {}", String::from_utf8_lossy(authorization), url),
+            None => url.clone(),
+        };
+
+        let mut conditional_headers = headers.clone();
+        if let Ok(cache) = cache.lock() {
+            if let Some(&(ref etag, _, _)) = cache.get(&key) {
+                conditional_headers.set_raw("If-None-Match", vec![etag.clone().into_bytes()]);
+            }
+        }
+        let mut response = self.send_request(Method::Get,
+                                             url.as_str(),
+                                             conditional_headers,
+                                             None::<&VoidDTO>)?;
+        if response.status == StatusCode::NotModified {
+            if let Ok(mut cache) = cache.lock() {
+                if let Some(&mut (_, ref body, ref mut last_used)) = cache.get_mut(&key) {
+                    *last_used = Instant::now();
+                    return Ok(body.clone());
+                }
+            }
+            // The cached response was evicted in the meantime, so it must be requested again.
+            response = self.send_request(Method::Get, url.as_str(), headers, None::<&VoidDTO>)?;
+        }
+
+        let body = self.read_body(&mut response)?;
+        let etag = response.headers
+            .get_raw("ETag")
+            .and_then(|values| values.first())
+            .and_then(|value| String::from_utf8(value.clone()).ok());
+        if let (Some(etag), Ok(mut cache)) = (etag, cache.lock()) {
+            if cache.len() >= ETAG_CACHE_SIZE && !cache.contains_key(&key) {
+                let least_recent = cache.iter()
+                    .min_by_key(|&(_, &(_, _, last_used))| last_used)
+                    .map(|(entry_key, _)| entry_key.clone());
+                if let Some(least_recent) = least_recent {
+                    let _ = cache.remove(&least_recent);
+                }
+            }
+            let _ = cache.insert(key, (etag, body.clone(), Instant::now()));
+        }
+        Ok(body)
+    }
+
+    /// Sends a request with the given DTO encoded as the JSON body.
+    ///
+    /// The DTO is encoded only once, and the same body is reused if the request is retried.
     fn send_request<S: AsRef<str>, D: DTO>(&self,
                                            method: Method,
                                            url: S,
-                                           mut headers: Headers,
+                                           headers: Headers,
                                            dto: Option<&D>)
-                                           -> Result<Response> {    
-        
+                                           -> Result<RawResponse> {
+        let body = match dto {
+            Some(d) => Some(json::encode(d)?),
+            None => None,
+        };
+        self.send_raw_request(method, url, headers, body.as_ref().map(|b| b.as_bytes()))
+    }
+
+    /// Sends a request with the given already serialized body.
+    ///
+    /// This is used by requests whose body is already JSON, such as `request_raw()`, so that it
+    /// doesn't need to be decoded and encoded again. The body is borrowed, so it's never copied,
+    /// not even when the request is retried or redirected.
+    fn send_raw_request<S: AsRef<str>>(&self,
+                                       mut method: Method,
+                                       url: S,
+                                       mut headers: Headers,
+                                       mut body: Option<&[u8]>)
+                                       -> Result<RawResponse> {
+        // Headers set by the request itself take precedence over the default ones.
+        for header in self.default_headers.iter() {
+            if headers.get_raw(header.name()).is_none() {
+                headers.set_raw(header.name().to_owned(), vec![header.value_string().into_bytes()]);
+            }
+        }
+        if let Some(ref language) = self.language {
+            if headers.get_raw("Accept-Language").is_none() {
+                headers.set_raw("Accept-Language", vec![language.clone().into_bytes()]);
+            }
+        }
         headers.set(Accept(vec![qitem(Mime(TopLevel::Application,
                                            SubLevel::Json,
                                            vec![(Attr::Charset, Value::Utf8)]))]));
-        let body = match dto {
-            Some(d) => Some(json::encode(d).unwrap()),
-            None => None,
+        // Only requests that can be safely repeated are retried, to avoid duplicating operations
+        // such as transactions when the request reached the server but the response got lost.
+        let retry = method.idempotent() || headers.get_raw(IDEMPOTENCY_KEY_HEADER).is_some();
+        if let Some(ref breaker) = self.circuit_breaker {
+            if !breaker.allow_request() {
+                return Err(Error::CircuitOpen);
+            }
+        }
+        let transport: &Transport = match self.transport {
+            Some(ref t) => &**t,
+            None => &self.client,
         };
-        let mut response = self.client
-            .request(method.clone(), url.as_ref())
-            .headers(headers.clone());
-        if let Some(ref b) = body {
-            response = response.body(b);
-        }
-        let mut response_main = response.send();
-        let mut bad_status = false;
-        let mut response = if response_main.is_err() {
-            while !bad_status {
-                let mut response = self.client
-                .request(method.clone(), url.as_ref())
-                .headers(headers.clone());
-                if let Some(ref b) = body {
-                    response = response.body(b);
+        let mut url = url.as_ref().to_owned();
+        let mut redirects = 0;
+        let mut response = loop {
+            let mut attempt = 0;
+            let response = loop {
+                attempt += 1;
+                match transport.send(method.clone(), &url, headers.clone(), body) {
+                    Ok(r) => break r,
+                    Err(e) => {
+                        // Retries stop once the circuit opens, instead of hammering the server.
+                        if let Some(ref breaker) = self.circuit_breaker {
+                            breaker.record_failure();
+                            if !breaker.allow_request() {
+                                return Err(e);
+                            }
+                        }
+                        // The predicate can only narrow the retries, so that non-idempotent
+                        // requests are never retried.
+                        let retry = retry && attempt < self.max_attempts;
+                        let retry = match self.retry_predicate {
+                            Some(ref predicate) => retry && (**predicate)(&e, attempt),
+                            None => retry,
+                        };
+                        if !retry {
+                            return Err(e);
+                        }
+                        thread::sleep(Duration::from_millis(RETRY_BACKOFF_MS <<
+                                                            cmp::min(attempt - 1, 10)));
+                    }
                 }
-                response_main = response.send();
-                if !response_main.is_err() {
-                    bad_status = true;
+            };
+            let location = match response.status.to_u16() {
+                301 | 302 | 303 | 307 | 308 => {
+                    response.headers
+                        .get_raw("Location")
+                        .and_then(|values| values.first())
+                        .and_then(|value| String::from_utf8(value.clone()).ok())
                 }
+                _ => None,
+            };
+            let location = match location {
+                Some(l) => l,
+                None => break response,
+            };
+            if redirects >= self.max_redirects {
+                // The server did respond, so the outcome must still be recorded, or a probe of a
+                // half open circuit would leave it half open forever.
+                self.record_response(&response);
+                return Err(Error::TooManyRedirects);
             }
-            response_main
-        } else {
-            response_main
-        }?;
-           
+            redirects += 1;
+
+            let next_url = resolve_redirect(&url, location.trim());
+            // Credentials must never be sent to a different origin than the one they were meant
+            // for, since a redirect could leak them to another host.
+            if origin(&next_url) != origin(&url) {
+                headers.remove_raw("Authorization");
+            }
+            if response.status == StatusCode::SeeOther {
+                method = Method::Get;
+                body = None;
+            }
+            url = next_url;
+        };
+        self.record_response(&response);
+
         match response.status {
-            StatusCode::Ok => Ok(response),
+            // Accepted responses are left to each endpoint to interpret.
+            StatusCode::Ok | StatusCode::Accepted => Ok(response),
+            // Successful operations without anything to return.
+            StatusCode::NoContent => Ok(response),
+            // Only sent for conditional requests, that will use their cached response.
+            StatusCode::NotModified => Ok(response),
+            // The server might not be able to send a JSON body while in maintenance.
+            StatusCode::ServiceUnavailable => {
+                Err(Error::Unavailable(retry_after(&response.headers)))
+            }
             status => {
-                let mut response_str = String::new();
-                let _ = response.read_to_string(&mut response_str)?;
+                // Responses to `HEAD` requests never have a body, and other responses might not
+                // have one either, so the reason of the status is used as the message then.
+                let response_str = if method == Method::Head {
+                    String::new()
+                } else {
+                    self.read_body(&mut response)?
+                };
+                let (code, message) = if response_str.trim().is_empty() {
+                    (None, String::from(status.canonical_reason().unwrap_or("unknown error")))
+                } else {
+                    let response_dto: ErrorResponseDTO = json::decode(&response_str)?;
+                    (response_dto.code, response_dto.message)
+                };
 
                 match status {
-                    StatusCode::Forbidden => {
-                        let response_dto: ResponseDTO = json::decode(&response_str)?;
-                        Err(Error::Forbidden(response_dto.message))
-                    }
-                    StatusCode::Accepted => {
-                        let response_dto: ResponseDTO = json::decode(&response_str)?;
-                        Err(Error::Client(response_dto.message))
-                    }
-                    StatusCode::BadRequest => {
-                        let response_dto: ResponseDTO = json::decode(&response_str)?;
-                        Err(Error::BadRequest(response_dto.message))
-                    }
-                    StatusCode::NotFound => {
-                        let response_dto: ResponseDTO = json::decode(&response_str)?;
-                        Err(Error::NotFound(response_dto.message))
-                    }
-                    _ => {                      
-                        let response_dto: ResponseDTO = json::decode(&response_str)?;
-                        Err(Error::Server(response_dto.message))
+                    StatusCode::Forbidden => Err(Error::Forbidden(message)),
+                    StatusCode::Unauthorized => Err(Error::Unauthorized(message)),
+                    StatusCode::BadRequest => Err(Error::BadRequest(message)),
+                    StatusCode::NotFound => Err(Error::NotFound(message)),
+                    _ => {
+                        Err(Error::Server {
+                            code: code,
+                            message: message,
+                        })
                     }
                 }
             }
         }
     }
 }
+
+/// Gets the time to wait before retrying the request from the `Retry-After` header, if present.
+///
+/// Only the delay in seconds format is supported, not the HTTP date format.
+fn retry_after(headers: &Headers) -> Option<Duration> {
+    headers.get_raw("Retry-After")
+        .and_then(|values| values.first())
+        .and_then(|value| str::from_utf8(value).ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Gets the origin of the given URL, that is, its scheme, host and port, in lowercase.
+fn origin(url: &str) -> String {
+    let authority_start = url.find("://").map(|i| i + 3).unwrap_or(0);
+    let authority_end = url[authority_start..]
+        .find(|c: char| c == '/' || c == '?' || c == '#')
+        .map(|i| authority_start + i)
+        .unwrap_or(url.len());
+    url[..authority_end].to_lowercase()
+}
+
+/// Resolves the `Location` of a redirect relative to the URL of the redirected request.
+fn resolve_redirect(url: &str, location: &str) -> String {
+    if location.contains("://") {
+        location.to_owned()
+    } else if location.starts_with('/') {
+        format!("{}{}", origin(url), location)
+    } else {
+        let path_end = url.find(|c: char| c == '?' || c == '#').unwrap_or(url.len());
+        let base = &url[..path_end];
+        match base.rfind('/') {
+            Some(i) if i >= origin(url).len() => format!("{}{}", &base[..i + 1], location),
+            _ => format!("{}/{}", base, location),
+        }
+    }
+}
+
+/// Percent-encodes the given string so that it can be used as a single segment of a URL path.
+///
+/// Only the unreserved characters are kept as they are, so slashes, question marks and the like
+/// can't change the endpoint the request is sent to. This also makes it safe as a query value.
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for &byte in segment.as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Computes the age in years at the given date of someone born on the given birthday.
+///
+/// The age will be negative if the birthday is after the given date.
+fn age_at(birthday: NaiveDate, date: NaiveDate) -> i32 {
+    let age = date.year() - birthday.year();
+    if (date.month(), date.day()) < (birthday.month(), birthday.day()) {
+        age - 1
+    } else {
+        age
+    }
+}
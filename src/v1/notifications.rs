@@ -0,0 +1,57 @@
+use hyper::method::Method;
+use hyper::header::{Headers, Authorization};
+
+use error::Result;
+use super::{Client, VoidDTO};
+use super::dtos::NotificationDTO;
+use super::types::Notification;
+use super::oauth::AccessToken;
+
+/// Methods for working with notifications.
+impl Client {
+    /// Gets the notifications of the given user, both read and unread
+    ///
+    /// This gives a single place to check for new friend requests, received transactions and
+    /// verification updates. The user can get their own notifications, and admins can get the
+    /// notifications of any user.
+    pub fn get_notifications(&self,
+                             access_token: &AccessToken,
+                             user_id: u64)
+                             -> Result<Vec<Notification>> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}notifications/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<NotificationDTO, Notification>(&mut response)
+    }
+
+    /// Marks the given notification as read
+    pub fn mark_notification_read(&self,
+                                  access_token: &AccessToken,
+                                  notification_id: u64)
+                                  -> Result<()> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let _ = self.send_request(Method::Post,
+                          format!("{}mark_notification_read/{}", self.url, notification_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(())
+    }
+
+    /// Marks all the notifications of the logged in user as read
+    pub fn mark_all_read(&self, access_token: &AccessToken) -> Result<()> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let _ = self.send_request(Method::Post,
+                          format!("{}mark_all_notifications_read", self.url),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(())
+    }
+}
@@ -3,7 +3,6 @@
 //! contains the required structs and enums for a typesafe OAuth with the API.
 use std::slice::Iter;
 use std::result::Result as StdResult;
-use std::io::Read;
 
 use hyper::header::Bearer;
 use hyper::method::Method;
@@ -22,6 +21,31 @@ use super::types::ClientInfo;
 /// Application's secret length.
 pub const SECRET_LEN: usize = 20;
 
+/// Parses a scope from its string representation.
+///
+/// This is the inverse of the `Display` implementation of `Scope`, so that
+/// `parse_scope(&format!("{}", scope))` returns the original scope.
+pub fn parse_scope(scope: &str) -> StdResult<Scope, FromDTOError> {
+    let scope = scope.trim();
+    if scope == format!("{}", Scope::Admin) {
+        return Ok(Scope::Admin);
+    }
+    if scope == format!("{}", Scope::Public) {
+        return Ok(Scope::Public);
+    }
+    // The prefix of user scopes is whatever precedes the ID in their string representation.
+    let user_prefix = format!("{}", Scope::User(0));
+    let user_prefix = &user_prefix[..user_prefix.len() - 1];
+    if scope.starts_with(user_prefix) {
+        if let Ok(id) = scope[user_prefix.len()..].parse() {
+            if format!("{}", Scope::User(id)) == scope {
+                return Ok(Scope::User(id));
+            }
+        }
+    }
+    Err(FromDTOError::new("the scope is not a valid scope"))
+}
+
 /// Struct representing an access token.
 #[derive(Debug, Clone)]
 pub struct AccessToken {
@@ -99,7 +123,12 @@ impl FromDTO<AccessTokenDTO> for AccessToken {
         if dto.token_type != TokenTypeDTO::Bearer {
             return Err(FromDTOError::new("the token type of the access token is not valid"));
         }
-        let scopes: Vec<Scope> = json::decode(&dto.scopes).unwrap();
+        let scopes: Vec<Scope> = match json::decode(&dto.scopes) {
+            Ok(s) => s,
+            Err(_) => {
+                return Err(FromDTOError::new("the scopes of the access token are not valid"))
+            }
+        };
         if scopes.is_empty() {
             return Err(FromDTOError::new("there were no scopes in the access token"));
         }
@@ -134,9 +163,7 @@ impl Client {
                                       format!("{}token", self.url),
                                       headers,
                                       None::<&VoidDTO>)?;
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Ok(AccessToken::from_dto(json::decode(&response_str)?)?)
+                    self.decode_dto::<AccessTokenDTO, AccessToken>(&mut response)
                 } else {
                     Err(Error::InvalidSecret)
                 }
@@ -167,12 +194,28 @@ impl Client {
                               format!("{}create_client", self.url),
                               headers,
                               Some(&dto))?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let client_dto: ClientInfoDTO = json::decode(&response_str)?;
-            Ok(ClientInfo::from_dto(client_dto)?)
+            self.decode_dto::<ClientInfoDTO, ClientInfo>(&mut response)
         } else {
             Err(Error::Forbidden(String::from("the token must be an unexpired admin token")))
         }
     }
+
+    /// Gets the current scopes of the given token from the server
+    ///
+    /// Unlike `AccessToken::scopes()`, that returns the scopes the token had when it was issued,
+    /// this asks the server for the scopes currently associated with the token, that might have
+    /// changed since then.
+    pub fn get_current_scopes(&self, access_token: &AccessToken) -> Result<Vec<Scope>> {
+        if !access_token.has_expired() {
+            let mut headers = Headers::new();
+            headers.set(Authorization(access_token.get_token()));
+            let mut response = self.send_request(Method::Get,
+                              format!("{}token_scopes", self.url),
+                              headers,
+                              None::<&VoidDTO>)?;
+            self.decode_response(&mut response)
+        } else {
+            Err(Error::Forbidden(String::from("the token must be an unexpired token")))
+        }
+    }
 }
@@ -1,19 +1,46 @@
-use std::io::Read;
 //use hyper::status::StatusCode;
 use hyper::method::Method;
 use hyper::header::{Headers, Authorization};
-use dto::{LoginDTO, RegisterDTO, ResetPasswordDTO, NewPasswordDTO,FromDTO,ResponseDTO};
+use dto::{LoginDTO, RegisterDTO, ResetPasswordDTO, NewPasswordDTO, AccessTokenDTO};
 //use hyper::client::response::Response;
-use error::{Result, Error};
-use super::{Client, VoidDTO};
+use error::{Result, Error, RegistrationError};
+use super::{Client, VoidDTO, encode_path_segment};
+use super::dtos::{SubscribeEmailDTO, ServerInfoDTO, RewardDTO};
 use super::oauth::AccessToken;
-use rustc_serialize::json;
+use super::types::{ApiMessage, ServerInfo, Reward};
 /// Public methods for the client.
 ///
 /// These are the public methods for getting a token, creating and logging in users, and confirming
 /// their information.
 impl Client {
+    /// Checks that the API is reachable
+    ///
+    /// This does not require any access token, so it can be used as a cheap health check before
+    /// sending any real request.
+    pub fn ping(&self) -> Result<()> {
+        let _ = self.send_request(Method::Get,
+                          format!("{}ping", self.url),
+                          Headers::new(),
+                          None::<&VoidDTO>)?;
+        Ok(())
+    }
+
+    /// Gets the information of the API server, including its version
+    ///
+    /// This does not require any access token. The version of this client library can be found in
+    /// the [`VERSION`](../constant.VERSION.html) constant.
+    pub fn get_server_info(&self) -> Result<ServerInfo> {
+        let mut response = self.send_request(Method::Get,
+                          format!("{}version", self.url),
+                          Headers::new(),
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<ServerInfoDTO, ServerInfo>(&mut response)
+    }
+
     /// Registers the user
+    ///
+    /// If the server rejects the registration for a known reason, such as the email being already
+    /// registered, an `Error::Registration` will be returned with the specific reason.
      pub fn register<U: Into<String>, P: Into<String>, E: Into<String>, R: Into<String>>
         (&self,
          access_token: &AccessToken,
@@ -22,27 +49,57 @@ impl Client {
          email: E,
          referer: Option<R>)
          -> Result<()> {
-        if access_token.is_public() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = RegisterDTO {
-                username: username.into(),
-                password: password.into(),
-                email: email.into(),
-                referer: referer.and_then(|pass| Some(pass.into())),
-            };
-            let _ = self.send_request(Method::Post,
-                              format!("{}register", self.url),
-                              headers,
-                              Some(&dto))?;
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = RegisterDTO {
+            username: username.into(),
+            password: password.into(),
+            email: email.into(),
+            referer: referer.and_then(|pass| Some(pass.into())),
+        };
+        match self.send_request(Method::Post,
+                                format!("{}register", self.url),
+                                headers,
+                                Some(&dto)) {
+            Ok(_) => Ok(()),
+            Err(Error::BadRequest(message)) => {
+                Err(match RegistrationError::from_message(&message) {
+                    Some(e) => Error::Registration(e),
+                    None => Error::BadRequest(message),
+                })
+            }
+            Err(Error::Forbidden(message)) => {
+                Err(match RegistrationError::from_message(&message) {
+                    Some(e) => Error::Registration(e),
+                    None => Error::Forbidden(message),
+                })
+            }
+            Err(e) => Err(e),
         }
     }
 
     // TODO register_encrypted
 
+    /// Checks if the given username is available for registration
+    ///
+    /// It sends a `HEAD` request, so no body is downloaded. Note that the username could be taken
+    /// by someone else before registering it, so `register()` can still fail.
+    pub fn username_available(&self, access_token: &AccessToken, username: &str) -> Result<bool> {
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let url = format!("{}username/{}", self.url, encode_path_segment(username));
+        match self.send_request(Method::Head,
+                                url,
+                                headers,
+                                None::<&VoidDTO>) {
+            Ok(_) => Ok(false),
+            Err(Error::NotFound(_)) => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Logs the user in
     pub fn login<UM: Into<String>, P: Into<String>>(&self,
                                                     access_token: &AccessToken,
@@ -50,82 +107,85 @@ impl Client {
                                                     password: P,
                                                     remember_me: bool)
                                                     -> Result<AccessToken> {
-        if access_token.is_public() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = LoginDTO {
-                user_email: user_email.into(),
-                password: password.into(),
-                remember_me: remember_me,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}login", self.url),
-                              headers,
-                              Some(&dto))?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            Ok(AccessToken::from_dto(json::decode(&response_str)?)?)
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
-        }
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = LoginDTO {
+            user_email: user_email.into(),
+            password: password.into(),
+            remember_me: remember_me,
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}login", self.url),
+                          headers,
+                          Some(&dto))?;
+        self.decode_dto::<AccessTokenDTO, AccessToken>(&mut response)
     }
 
-     /// Confirms the users email
+    /// Logs the user out
+    ///
+    /// Invalidates the session of the user in the server, including any remember-me persistence.
+    /// The given token will no longer be valid after this call.
+    pub fn logout(&self, access_token: &AccessToken) -> Result<()> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let _ = self.send_request(Method::Post,
+                          format!("{}logout", self.url),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(())
+    }
+
+    /// Confirms the email of the user with the key sent to it
+    ///
+    /// The message sent by the server is returned, so that it can be shown to the user.
     pub fn confirm_email<S: AsRef<str>>(&self,
                                         access_token: &AccessToken,
                                         email_key: S)
-                                        -> Result<()> {
-        if access_token.is_public() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let _ = self.send_request(Method::Post,
-                              format!("{}confirm_email/{}", self.url, email_key.as_ref()),
-                              headers,
-                              None::<&VoidDTO>)?;
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
-        }
+                                        -> Result<ApiMessage> {
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Post,
+                          format!("{}confirm_email/{}", self.url, email_key.as_ref()),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_message(&mut response)
     }
 
-     /// unConfirms the users email
+    /// Unconfirms the email of the user with the key sent to it
     pub fn unconfirm_email<S: AsRef<str>>(&self,
                                         access_token: &AccessToken,
                                         email_key: S)
-                                        -> Result<()> {
-        if access_token.is_public() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let _ = self.send_request(Method::Post,
-                              format!("{}unconfirm_email/{}", self.url, email_key.as_ref()),
-                              headers,
-                              None::<&VoidDTO>)?;
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
-        }
+                                        -> Result<ApiMessage> {
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Post,
+                          format!("{}unconfirm_email/{}", self.url, email_key.as_ref()),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_message(&mut response)
     }
 
 
-    /// Begins a the reset password procecss
+    /// Begins the reset password process
     pub fn start_reset_password<E: Into<String>>(&self,
                                                 access_token: &AccessToken,
                                                 email: E)
-                                                -> Result<()> {
-        if access_token.is_public() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = ResetPasswordDTO {
-                email: email.into(),
-            };
-            let _ = self.send_request(Method::Post,
-                              format!("{}start_reset_password", self.url),
-                              headers,
-                              Some(&dto))?;
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
-        }
+                                                -> Result<ApiMessage> {
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = ResetPasswordDTO {
+            email: email.into(),
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}start_reset_password", self.url),
+                          headers,
+                          Some(&dto))?;
+        self.decode_message(&mut response)
     }
 
     /// Attempts to confirm the new password reset
@@ -133,89 +193,82 @@ impl Client {
                                                           access_token: &AccessToken,
                                                           pass_key: K,
                                                           new_password: P)
-                                                          -> Result<()> {
-        if access_token.is_public() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = NewPasswordDTO { new_password: new_password.into() };
-            let _ = self.send_request(Method::Post,
-                              format!("{}reset_password/{}", self.url, pass_key.as_ref()),
-                              headers,
-                              Some(&dto))?;
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
-        }
+                                                          -> Result<ApiMessage> {
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = NewPasswordDTO { new_password: new_password.into() };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}reset_password/{}", self.url, pass_key.as_ref()),
+                          headers,
+                          Some(&dto))?;
+        self.decode_message(&mut response)
     }
 
 
-   ///Subscribe Maililng List
+    /// Subscribes the given email to the mailing list
     pub fn subscribe_email <EM: Into<String>, UN: Into<String>>(&self,
                                         access_token: &AccessToken,
                                         user_email: EM,
                                         user_name: UN)
                                         -> Result<()> {
-        if access_token.is_public() {
-            let mut headers = Headers::new();
-            //using LoginDTO temporarily, new dto will be introduced in future
-            let dto = LoginDTO {
-                user_email: user_email.into(),
-                password: user_name.into(),
-                remember_me: false,
-            };
-            headers.set(Authorization(access_token.get_token()));
-            let _ = self.send_request(Method::Post,
-                              format!("{}subscribe_email", self.url),
-                              headers,
-                              Some(&dto))?;                             
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
-        }
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        let dto = SubscribeEmailDTO {
+            email: user_email.into(),
+            name: user_name.into(),
+        };
+        headers.set(Authorization(access_token.get_token()));
+        let _ = self.send_request(Method::Post,
+                          format!("{}subscribe_email", self.url),
+                          headers,
+                          Some(&dto))?;
+        Ok(())
     }
 
-    /// Confirms the users subscription
+    /// Confirms the mailing list subscription with the key sent to the email
     pub fn confirm_subscribe_email<S: AsRef<str>>(&self,
                                         access_token: &AccessToken,
                                         email_key: S)
                                         -> Result<()> {
-        if access_token.is_public(){
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let _ = self.send_request(Method::Post,
-                              format!("{}confirm_subscribe_email/{}", self.url, email_key.as_ref()),
-                              headers,
-                              None::<&VoidDTO>)?;
-                              
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
-        }
+        self.require_public(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let _ = self.send_request(Method::Post,
+                          format!("{}confirm_subscribe_email/{}", self.url, email_key.as_ref()),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(())
     }
-	
-	 /// Confirms the users subscription
+
+    /// Gets the value of the next reward of the given user, as a message
+    ///
+    /// The value will be sent in the message as a string. The
+    /// [`get_next_reward()`](#method.get_next_reward) method should be preferred, since it returns
+    /// the typed reward.
     pub fn get_next_reward_value(&self,
                                  access_token: &AccessToken,
                                  user_id: u64)
-                                        -> Result<(ResponseDTO)> {
-		println!("Apirs\nAccess token is{:?}",access_token);
-        let id = access_token.get_user_id();
-        if id.is_some() && !access_token.has_expired() {
-			println!("access_token is valid");
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}get_next_reward/{}", self.url, user_id),
-                              headers,
-                              None::<&VoidDTO>)?;
-			
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-			let res: ResponseDTO = json::decode(&response_str)?;
-            Ok(res)
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired public token")))
-        }
+                                 -> Result<ApiMessage> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}get_next_reward/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_message(&mut response)
+    }
+
+    /// Gets the next reward of the given user
+    pub fn get_next_reward(&self, access_token: &AccessToken, user_id: u64) -> Result<Reward> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}next_reward/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<RewardDTO, Reward>(&mut response)
     }
-    
 }
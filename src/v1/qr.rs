@@ -0,0 +1,32 @@
+//! QR code module for the Fractal API.
+//!
+//! Contains the helpers to generate the payloads of the QR codes used to receive credits and to
+//! authenticate pending transactions. Only the payload strings are generated, so that they can be
+//! encoded with any QR code library.
+
+use utils::{WalletAddress, Amount};
+
+use super::encode_path_segment;
+
+/// URI scheme used in the QR code payloads.
+pub const URI_SCHEME: &'static str = "fractal";
+
+/// Generates the payment URI for the given wallet address.
+///
+/// The URI will have the `fractal:<address>` format, with an optional `amount` parameter if the
+/// amount to receive is known, such as `fractal:<address>?amount=<amount>`.
+pub fn payment_uri(address: &WalletAddress, amount: Option<Amount>) -> String {
+    match amount {
+        Some(a) => format!("{}:{}?amount={}", URI_SCHEME, address, a),
+        None => format!("{}:{}", URI_SCHEME, address),
+    }
+}
+
+/// Generates the URI for the given pending transaction code.
+///
+/// The code is the one returned when generating a new transaction, and the URI will have the
+/// `fractal:transaction?code=<code>` format. The code is percent-encoded, so that it can't add
+/// parameters to the URI.
+pub fn transaction_code_uri<S: AsRef<str>>(code: S) -> String {
+    format!("{}:transaction?code={}", URI_SCHEME, encode_path_segment(code.as_ref()))
+}
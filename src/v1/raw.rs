@@ -0,0 +1,39 @@
+use hyper::method::Method;
+use hyper::header::{Headers, Authorization};
+
+use error::{Result, Error};
+use super::Client;
+use super::oauth::AccessToken;
+
+/// Raw request methods for the client.
+impl Client {
+    /// Sends an arbitrary authenticated request to the API, returning the raw response body.
+    ///
+    /// The request will be sent to the given path, relative to the API URL of the client, with the
+    /// given access token and the optional JSON body. It will go through the same retry and status
+    /// code handling as the rest of the methods of the client.
+    ///
+    /// **This is an unstable escape hatch** for endpoints that have no typed wrapper in this crate
+    /// yet. Its behaviour is tied to the server and might change without notice, so typed methods
+    /// should be preferred whenever they are available.
+    pub fn request_raw<P: AsRef<str>>(&self,
+                                      access_token: &AccessToken,
+                                      method: Method,
+                                      path: P,
+                                      body: Option<&str>)
+                                      -> Result<String> {
+        if !access_token.has_expired() {
+            let path = path.as_ref();
+            let path = if path.starts_with('/') { &path[1..] } else { path };
+            let mut headers = Headers::new();
+            headers.set(Authorization(access_token.get_token()));
+            let mut response = self.send_raw_request(method,
+                              format!("{}{}", self.url, path),
+                              headers,
+                              body.map(|b| b.as_bytes()))?;
+            self.read_body(&mut response)
+        } else {
+            Err(Error::Forbidden(String::from("the token must be an unexpired token")))
+        }
+    }
+}
@@ -1,18 +1,20 @@
-use std::io::Read;
-use std::str::FromStr;
+use std::{thread, vec};
+use std::time::{Duration, Instant};
 use hyper::method::Method;
 use hyper::header::{Headers, Authorization};
 use hyper::status::StatusCode;
-use rustc_serialize::json;
+use chrono::{DateTime, UTC};
 
 use utils::{WalletAddress, Amount};
-use dto::{FromDTO, GenerateTransactionDTO, TransactionDTO, PendingTransactionDTO,
+use dto::{GenerateTransactionDTO, TransactionDTO, PendingTransactionDTO,
           AuthenticationCodeDTO, ResponseDTO};
 
-use super::{Client, VoidDTO};
+use super::{Client, VoidDTO, IDEMPOTENCY_KEY_HEADER, encode_path_segment};
+use super::dtos::{EstimateFeeDTO, FeeDTO, InternalTransferDTO, PendingTransactionInfoDTO,
+                  WalletAddressBatchDTO, WalletAddressOwnerDTO};
 
 use error::{Result, Error};
-use super::types::Transaction;
+use super::types::{Transaction, PendingTransaction, ApiMessage, WalletKind};
 use super::oauth::AccessToken;
 
 /// Methods for working with transactions.
@@ -30,64 +32,183 @@ impl Client {
                               format!("{}transaction/{}", self.url, transaction_id),
                               headers,
                               None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let transaction: TransactionDTO = json::decode(&response_str)?;
             match response.status {
-                StatusCode::Ok => {
-                    Ok(Transaction::from_dto(transaction)?)
-                }
                 StatusCode::Accepted => {
-                    Err(Error::Accepted(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
-                StatusCode::Unauthorized => {
-                    Err(Error::Unauthorized(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
-                _ => {
-                    Err(Error::Forbidden(json::decode::<ResponseDTO>(&response_str)?.message))
+                    let res: ResponseDTO = self.decode_response(&mut response)?;
+                    Err(Error::Accepted(res.message))
                 }
-            } 
-            //Ok(Transaction::from_dto(transaction)?)
+                _ => self.decode_dto::<TransactionDTO, Transaction>(&mut response),
+            }
         } else {
             Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
                                                token")))
         }
     }
 
+    /// Gets the pending transaction with the given code
+    ///
+    /// This allows checking a transaction generated with `new_transaction()` before it is
+    /// confirmed, since it does not have an ID until then.
+    pub fn get_pending_transaction(&self,
+                                   access_token: &AccessToken,
+                                   code: &str)
+                                   -> Result<PendingTransaction> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}pending_transaction/{}", self.url, encode_path_segment(code)),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<PendingTransactionInfoDTO, PendingTransaction>(&mut response)
+    }
+
+    /// Gets all the pending transactions of the given user
+    ///
+    /// These are the transactions generated by the user that have not been confirmed yet.
+    pub fn get_pending_transactions(&self,
+                                    access_token: &AccessToken,
+                                    user_id: u64)
+                                    -> Result<Vec<PendingTransaction>> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}pending_transactions/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<PendingTransactionInfoDTO, PendingTransaction>(&mut response)
+    }
+
+    /// Waits until the given transaction is confirmed, and returns it
+    ///
+    /// It will poll the transaction every `poll_interval` until it is confirmed, or until the
+    /// `timeout` elapses, returning `Error::Timeout` in that case. Errors other than the
+    /// transaction not being found or not being confirmed yet are returned right away.
+    pub fn wait_for_transaction(&self,
+                                access_token: &AccessToken,
+                                transaction_id: u64,
+                                timeout: Duration,
+                                poll_interval: Duration)
+                                -> Result<Transaction> {
+        let start = Instant::now();
+        loop {
+            match self.get_transaction(access_token, transaction_id) {
+                Ok(transaction) => return Ok(transaction),
+                Err(Error::NotFound(_)) |
+                Err(Error::Accepted(_)) => {}
+                Err(e) => return Err(e),
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::Timeout);
+            }
+            let remaining = timeout - elapsed;
+            thread::sleep(if poll_interval < remaining {
+                poll_interval
+            } else {
+                remaining
+            });
+        }
+    }
+
     /// Generates a new transaction. Returns the code of the transaction
+    ///
+    /// The request will not be retried on connection errors, since it could generate a duplicated
+    /// transaction. Use `new_transaction_idempotent()` for safely retryable transactions.
     pub fn new_transaction(&self,
                            access_token: &AccessToken,
                            receiver_wallet: WalletAddress,
                            receiver_id: u64,
                            amount: Amount)
                            -> Result<String> {
+        self.generate_transaction(access_token, receiver_wallet, receiver_id, amount, None)
+    }
+
+    /// Generates a new transaction with the given idempotency key. Returns the code of the
+    /// transaction
+    ///
+    /// The key is sent in the `Idempotency-Key` header, so that the server can detect duplicated
+    /// requests. This makes it safe to retry the request on connection errors, and also to call
+    /// this method again with the same key if the result of a previous call is unknown. The key
+    /// should be unique for each transaction.
+    pub fn new_transaction_idempotent(&self,
+                                      access_token: &AccessToken,
+                                      receiver_wallet: WalletAddress,
+                                      receiver_id: u64,
+                                      amount: Amount,
+                                      key: &str)
+                                      -> Result<String> {
+        self.generate_transaction(access_token, receiver_wallet, receiver_id, amount, Some(key))
+    }
+
+    fn generate_transaction(&self,
+                            access_token: &AccessToken,
+                            receiver_wallet: WalletAddress,
+                            receiver_id: u64,
+                            amount: Amount,
+                            idempotency_key: Option<&str>)
+                            -> Result<String> {
         let user_id = access_token.get_user_id();
-        if user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = GenerateTransactionDTO {
-                origin_id: user_id.unwrap(),
-                destination_address: receiver_wallet,
-                destination_id: receiver_id,
-                amount: amount,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}new_transaction", self.url),
-                              headers,
-                              Some(&dto))?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(json::decode::<PendingTransactionDTO>(&response_str)?.code)
-                }
-                _ => {
-                    Err(Error::Forbidden(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
-            }   
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user  token")))
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        if let Some(key) = idempotency_key {
+            headers.set_raw(IDEMPOTENCY_KEY_HEADER, vec![key.as_bytes().to_vec()]);
         }
+        let dto = GenerateTransactionDTO {
+            origin_id: user_id.unwrap(),
+            destination_address: receiver_wallet,
+            destination_id: receiver_id,
+            amount: amount,
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}new_transaction", self.url),
+                          headers,
+                          Some(&dto))?;
+        Ok(self.decode_response::<PendingTransactionDTO>(&mut response)?.code)
+    }
+
+    /// Transfers the given amount between two wallets of the user. Returns the code of the
+    /// transaction
+    pub fn internal_transfer(&self,
+                             access_token: &AccessToken,
+                             from: WalletKind,
+                             to: WalletKind,
+                             amount: Amount)
+                             -> Result<String> {
+        if from == to {
+            return Err(Error::BadRequest(String::from("the origin and destination wallets must \
+                                                       be different")));
+        }
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = InternalTransferDTO {
+            from: format!("{}", from),
+            to: format!("{}", to),
+            amount: amount,
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}internal_transfer", self.url),
+                          headers,
+                          Some(&dto))?;
+        Ok(self.decode_response::<PendingTransactionDTO>(&mut response)?.code)
+    }
+
+    /// Estimates the fee of a transaction of the given amount
+    ///
+    /// The returned fee will be charged on top of the amount of the transaction.
+    pub fn estimate_fee(&self, access_token: &AccessToken, amount: Amount) -> Result<Amount> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = EstimateFeeDTO { amount: amount };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}estimate_fee", self.url),
+                          headers,
+                          Some(&dto))?;
+        Ok(self.decode_response::<FeeDTO>(&mut response)?.fee)
     }
 
     /// Gets all the transactions since the given transaction
@@ -95,24 +216,52 @@ impl Client {
                                 access_token: &AccessToken,
                                 first_transaction: u64)
                                 -> Result<Vec<Transaction>> {
-        if access_token.is_admin() && !access_token.has_expired() {
-                        let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}all_transactions/{}", self.url, first_transaction),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let transactions: Vec<TransactionDTO> = json::decode(&response_str)?;
-            Ok(transactions.into_iter()
-                .map(|t| Transaction::from_dto(t).unwrap())
-                .collect())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin token")))
+        self.require_admin(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}all_transactions/{}", self.url, first_transaction),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<TransactionDTO, Transaction>(&mut response)
+    }
+
+    /// Gets a lazy iterator through all the transactions since the given transaction
+    ///
+    /// Unlike `get_all_transactions()`, the transactions are fetched in pages of
+    /// `TRANSACTION_PAGE_SIZE` transactions as the iterator advances, so the whole list is never
+    /// kept in memory. If a page can't be fetched, the error is yielded and the iteration ends.
+    pub fn iter_all_transactions<'a>(&'a self,
+                                     access_token: &'a AccessToken,
+                                     first_transaction: u64)
+                                     -> TransactionIter<'a> {
+        TransactionIter {
+            client: self,
+            access_token: access_token,
+            next_id: first_transaction,
+            page: Vec::new().into_iter(),
+            finished: false,
         }
     }
 
+    fn get_transactions_page(&self,
+                             access_token: &AccessToken,
+                             first_transaction: u64,
+                             limit: usize)
+                             -> Result<Vec<Transaction>> {
+        self.require_admin(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}all_transactions/{}?limit={}",
+                                  self.url,
+                                  first_transaction,
+                                  limit),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<TransactionDTO, Transaction>(&mut response)
+    }
+
     /// Gets all the user transactions for the userId
     pub fn get_user_transactions(&self,
                                 access_token: &AccessToken,
@@ -125,82 +274,170 @@ impl Client {
                               format!("{}transactions_summary/{}", self.url, user_id),
                               headers,
                               None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let transactions: Vec<TransactionDTO> = json::decode(&response_str)?;
-            Ok(transactions.into_iter()
-                .map(|t| Transaction::from_dto(t).unwrap())
-                .collect())
+            self.decode_dto_list::<TransactionDTO, Transaction>(&mut response)
         } else {
             Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
         }
     }
 
+    /// Gets all the transactions of the given user between the given dates, both included
+    pub fn get_transactions_between(&self,
+                                    access_token: &AccessToken,
+                                    user_id: u64,
+                                    from: DateTime<UTC>,
+                                    to: DateTime<UTC>)
+                                    -> Result<Vec<Transaction>> {
+        if from > to {
+            return Err(Error::BadRequest(String::from("the start of the date range must not be \
+                                                       after its end")));
+        }
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}transactions/{}?from={}&to={}",
+                                  self.url,
+                                  user_id,
+                                  from.timestamp(),
+                                  to.timestamp()),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<TransactionDTO, Transaction>(&mut response)
+    }
+
     /// Authenticates the pending transaction
     pub fn authenticate_transaction<S: AsRef<str>>(&self,
                                                    access_token: &AccessToken,
                                                    transaction_key: S,
                                                    code: u32)
-                                                   -> Result<(String)> {
-        let user_id = access_token.get_user_id();
-        if user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = AuthenticationCodeDTO { code: code };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}authenticate_transaction/{}",
-                                      self.url,
-                                      transaction_key.as_ref()),
-                              headers,
-                              Some(&dto))?;
-            
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let res: ResponseDTO = json::decode(&response_str)?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(res.message)
-                }
-                StatusCode::Accepted => {
-                    Err(Error::Accepted(res.message))
-                }
-                _ => {
-                    Err(Error::Forbidden(res.message))
-                }
-            }   
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
+                                                   -> Result<ApiMessage> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = AuthenticationCodeDTO { code: code };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}authenticate_transaction/{}",
+                                  self.url,
+                                  transaction_key.as_ref()),
+                          headers,
+                          Some(&dto))?;
+        
+        let message = self.decode_message(&mut response)?;
+        match response.status {
+            StatusCode::Ok => Ok(message),
+            StatusCode::Accepted => Err(Error::Accepted(message.into_message())),
+            _ => Err(Error::Forbidden(message.into_message())),
         }
     }
 
 
+    /// Requests the approval of the pending transaction through the authenticator app
+    ///
+    /// Unlike `authenticate_transaction()`, which confirms the transaction with a code typed by
+    /// the user, this sends an approval request to the authenticator app of the user, where the
+    /// transaction can be approved without typing any code. The transaction will only be
+    /// completed once the user approves it in the app, so `get_transaction()` should be used to
+    /// check its result.
+    pub fn approve_transaction<S: AsRef<str>>(&self,
+                                              access_token: &AccessToken,
+                                              transaction_key: S)
+                                              -> Result<()> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let _ = self.send_request(Method::Post,
+                          format!("{}approve_transaction/{}",
+                                  self.url,
+                                  transaction_key.as_ref()),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(())
+    }
+
     /// Checks if the given wallet address is a valid wallet address and returns its associated
     /// user id
+    ///
+    /// An `Error::NotFound` will be returned if the address does not belong to any user. Use
+    /// `resolve_wallet_addresses()` to resolve many addresses at once.
     pub fn get_user_id_from_wallet_address(&self,
                                            access_token: &AccessToken,
                                            wallet_address: WalletAddress)
                                            -> Result<u64> {
-        let user_id = access_token.get_user_id();
-        if user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Post,
-                              format!("{}check_wallet_address/{}", self.url, wallet_address),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let res: ResponseDTO = json::decode(&response_str)?;
-    
-
-            match u64::from_str(&res.message) {
-                Ok(d) => Ok(d),
-                Err(_) => {
-                    Err(Error::BadRequest(String::from(res.message)))
+        let address = format!("{}", wallet_address);
+        let resolved = self.resolve_wallet_addresses(access_token, &[address.as_str()])?;
+        match resolved.into_iter().next() {
+            Some((_, Some(user_id))) => Ok(user_id),
+            _ => {
+                Err(Error::NotFound(format!("the wallet address `{}` does not belong to any user",
+                                            address)))
+            }
+        }
+    }
+
+    /// Resolves the given wallet addresses to the IDs of their users, in a single request
+    ///
+    /// Each address is returned along with the ID of its user, in the same order, or `None` if it
+    /// does not belong to any user.
+    pub fn resolve_wallet_addresses(&self,
+                                    access_token: &AccessToken,
+                                    addrs: &[&str])
+                                    -> Result<Vec<(String, Option<u64>)>> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = WalletAddressBatchDTO {
+            addresses: addrs.iter().map(|a| String::from(*a)).collect(),
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}check_wallet_addresses", self.url),
+                          headers,
+                          Some(&dto))?;
+        let owners: Vec<WalletAddressOwnerDTO> = self.decode_response(&mut response)?;
+        Ok(owners.into_iter().map(|o| (o.address, o.user_id)).collect())
+    }
+}
+
+/// Number of transactions fetched per page by `TransactionIter`.
+pub const TRANSACTION_PAGE_SIZE: usize = 100;
+
+/// Lazy iterator through all the transactions since a given transaction.
+///
+/// It is created with `Client::iter_all_transactions()`, and requires an admin token.
+pub struct TransactionIter<'a> {
+    client: &'a Client,
+    access_token: &'a AccessToken,
+    next_id: u64,
+    page: vec::IntoIter<Transaction>,
+    finished: bool,
+}
+
+impl<'a> Iterator for TransactionIter<'a> {
+    type Item = Result<Transaction>;
+
+    fn next(&mut self) -> Option<Result<Transaction>> {
+        if let Some(transaction) = self.page.next() {
+            return Some(Ok(transaction));
+        }
+        if self.finished {
+            return None;
+        }
+        match self.client.get_transactions_page(self.access_token,
+                                                 self.next_id,
+                                                 TRANSACTION_PAGE_SIZE) {
+            Ok(page) => {
+                if page.len() < TRANSACTION_PAGE_SIZE {
+                    self.finished = true;
                 }
+                if let Some(last_id) = page.iter().map(|t| t.get_id()).max() {
+                    self.next_id = last_id + 1;
+                }
+                self.page = page.into_iter();
+                self.page.next().map(Ok)
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
             }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
         }
     }
 }
@@ -0,0 +1,108 @@
+//! Transport module for the Fractal API.
+//!
+//! Contains the abstraction over the HTTP client used to send the requests, so that the client can
+//! be used with a custom transport, for example, to return canned responses in tests.
+
+use std::io::{self, Read};
+
+use hyper::Client as HyperClient;
+use hyper::method::Method;
+use hyper::header::Headers;
+use hyper::status::StatusCode;
+
+use error::Result;
+
+/// Raw HTTP response returned by a transport.
+///
+/// The body of the response can be read through its `Read` implementation.
+pub struct RawResponse {
+    /// Status code of the response.
+    pub status: StatusCode,
+    /// Headers of the response.
+    pub headers: Headers,
+    body: Box<Read + Send>,
+}
+
+impl RawResponse {
+    /// Creates a new raw response with the given status code, headers and body.
+    pub fn new<R: Read + Send + 'static>(status: StatusCode,
+                                         headers: Headers,
+                                         body: R)
+                                         -> RawResponse {
+        RawResponse {
+            status: status,
+            headers: headers,
+            body: Box::new(body),
+        }
+    }
+}
+
+impl Read for RawResponse {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+/// Transport used by the client to send the HTTP requests.
+///
+/// It is implemented for the hyper client, that is used by default. A custom transport can be set
+/// with `Client::with_transport()`.
+///
+/// ```
+/// # extern crate hyper;
+/// # extern crate fractal_api;
+/// use std::io;
+/// use fractal_api::Client;
+/// use fractal_api::error::Result;
+/// use fractal_api::v1::transport::{Transport, RawResponse};
+/// use hyper::method::Method;
+/// use hyper::header::Headers;
+/// use hyper::status::StatusCode;
+///
+/// struct PingTransport;
+///
+/// impl Transport for PingTransport {
+///     fn send(&self,
+///             _: Method,
+///             _: &str,
+///             _: Headers,
+///             _: Option<&[u8]>)
+///             -> Result<RawResponse> {
+///         Ok(RawResponse::new(StatusCode::Ok, Headers::new(), io::empty()))
+///     }
+/// }
+///
+/// # fn main() {
+/// let client = Client::with_transport("https://api.example.com/", PingTransport).unwrap();
+/// assert!(client.ping().is_ok());
+/// # }
+/// ```
+pub trait Transport: Send + Sync {
+    /// Sends a request with the given method, URL, headers and optional body.
+    fn send(&self,
+            method: Method,
+            url: &str,
+            headers: Headers,
+            body: Option<&[u8]>)
+            -> Result<RawResponse>;
+}
+
+impl Transport for HyperClient {
+    fn send(&self,
+            method: Method,
+            url: &str,
+            headers: Headers,
+            body: Option<&[u8]>)
+            -> Result<RawResponse> {
+        let mut request = self.request(method, url).headers(headers);
+        if let Some(b) = body {
+            request = request.body(b);
+        }
+        let response = request.send()?;
+        Ok(RawResponse {
+            status: response.status,
+            headers: response.headers.clone(),
+            body: Box::new(response),
+        })
+    }
+}
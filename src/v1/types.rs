@@ -1,7 +1,33 @@
 //! Types returned by the API.
 //!
 //! This module contains all the types required by the API to enable an easier use of it.
+//!
+//! # Times
+//!
+//! All the times, such as transaction timestamps or user registration times, are decoded from
+//! the `fractal_dto` objects with the `rustc-serialize` implementation of `chrono`. This is not an
+//! RFC 3339 string, but the structured form of a `DateTime<UTC>`, so the times are always in UTC
+//! and there is no time zone offset to interpret. Times sent as strings are rejected instead of
+//! being silently interpreted in some time zone:
+//!
+//! ```
+//! # extern crate chrono;
+//! # extern crate rustc_serialize;
+//! use chrono::{DateTime, UTC, TimeZone};
+//! use rustc_serialize::json;
+//!
+//! # fn main() {
+//! let time = UTC.ymd(2016, 5, 1).and_hms(8, 30, 0);
+//! let encoded = json::encode(&time).unwrap();
+//! assert_eq!(json::decode::<DateTime<UTC>>(&encoded).unwrap(), time);
+//!
+//! assert!(json::decode::<DateTime<UTC>>("\"2016-05-01T10:30:00+02:00\"").is_err());
+//! # }
+//! ```
 
+use std::fmt;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
 use std::collections::{btree_set, BTreeSet};
 use std::slice::Iter;
 use std::result::Result as StdResult;
@@ -11,8 +37,28 @@ use chrono::{DateTime, UTC, NaiveDate};
 use rustc_serialize::json;
 
 use dto::{UserDTO, FromDTO, FromDTOError, ScopeDTO as Scope, ClientInfoDTO, TransactionDTO,
-          ProfileDTO, PendingFriendRequestDTO};
-use utils::{WalletAddress, Amount, Address};
+          ProfileDTO, PendingFriendRequestDTO, ResponseDTO};
+pub use utils::{WalletAddress, Amount, Address};
+
+use error::{Result, Error};
+use super::age_at;
+use super::dtos::{AuthenticatorEnrollmentDTO, ServerInfoDTO, WalletDTO, RewardDTO,
+                  PendingTransactionInfoDTO, BalancesDTO, NotificationDTO, PlatformStatsDTO,
+                  NotificationPrefsDTO};
+
+/// Parses a wallet address from its string representation.
+///
+/// This is the same as using `FromStr` on the `WalletAddress`, but the error is converted to the
+/// error type of this crate, returning an `Error::BadRequest` if the address is not valid.
+pub fn parse_wallet_address<S: AsRef<str>>(address: S) -> Result<WalletAddress> {
+    match address.as_ref().parse() {
+        Ok(a) => Ok(a),
+        Err(_) => {
+            Err(Error::BadRequest(format!("the wallet address `{}` is not valid",
+                                          address.as_ref())))
+        }
+    }
+}
 
 /// Information about the API client.
 #[derive(Clone, Debug)]
@@ -73,6 +119,183 @@ impl FromDTO<ClientInfoDTO> for ClientInfo {
     }
 }
 
+/// Information about the API server.
+#[derive(Clone, Debug)]
+pub struct ServerInfo {
+    version: String,
+    api_version: String,
+}
+
+impl ServerInfo {
+    /// Gets the version of the server build.
+    pub fn get_version(&self) -> &str {
+        &self.version
+    }
+
+    /// Gets the version of the API served by the server.
+    pub fn get_api_version(&self) -> &str {
+        &self.api_version
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for ServerInfo {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("version"), self.version.to_json());
+        let _ = object.insert(String::from("api_version"), self.api_version.to_json());
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<ServerInfoDTO> for ServerInfo {
+    fn from_dto(dto: ServerInfoDTO) -> StdResult<ServerInfo, FromDTOError> {
+        Ok(ServerInfo {
+            version: dto.version,
+            api_version: dto.api_version,
+        })
+    }
+}
+
+/// Message sent by the server on a successful operation.
+///
+/// The message will be empty if the server did not send any body in the response.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ApiMessage {
+    message: String,
+}
+
+impl ApiMessage {
+    /// Gets the message sent by the server.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Consumes the API message, returning the message string.
+    pub fn into_message(self) -> String {
+        self.message
+    }
+}
+
+impl fmt::Display for ApiMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for ApiMessage {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("message"), self.message.to_json());
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<ResponseDTO> for ApiMessage {
+    fn from_dto(dto: ResponseDTO) -> StdResult<ApiMessage, FromDTOError> {
+        Ok(ApiMessage { message: dto.message })
+    }
+}
+
+/// Authenticator enrollment information.
+///
+/// Contains the secret of the authenticator, to be shown for manual entry, and the `otpauth://`
+/// URL, to be used for QR code generation.
+#[derive(Clone, Debug)]
+pub struct AuthenticatorEnrollment {
+    secret: String,
+    otpauth_url: String,
+}
+
+impl AuthenticatorEnrollment {
+    /// Gets the secret of the authenticator.
+    pub fn get_secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// Gets the `otpauth://` URL of the authenticator.
+    pub fn get_otpauth_url(&self) -> &str {
+        &self.otpauth_url
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for AuthenticatorEnrollment {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("secret"), self.secret.to_json());
+        let _ = object.insert(String::from("otpauth_url"), self.otpauth_url.to_json());
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<AuthenticatorEnrollmentDTO> for AuthenticatorEnrollment {
+    fn from_dto(dto: AuthenticatorEnrollmentDTO)
+                -> StdResult<AuthenticatorEnrollment, FromDTOError> {
+        Ok(AuthenticatorEnrollment {
+            secret: dto.secret,
+            otpauth_url: dto.otpauth_url,
+        })
+    }
+}
+
+/// Verification status of a user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerificationStatus {
+    /// The user has not started the verification process.
+    Unverified,
+    /// The verification of the user is pending.
+    Pending,
+    /// The user has been verified.
+    Verified,
+    /// The verification of the user has been rejected.
+    Rejected,
+    /// Unknown verification status, as sent by the server.
+    Unknown(String),
+}
+
+impl VerificationStatus {
+    /// Returns wether the user has been verified or not.
+    pub fn is_verified(&self) -> bool {
+        *self == VerificationStatus::Verified
+    }
+}
+
+impl<'a> From<&'a str> for VerificationStatus {
+    fn from(status: &'a str) -> VerificationStatus {
+        match status.trim().to_lowercase().as_str() {
+            "unverified" => VerificationStatus::Unverified,
+            "pending" => VerificationStatus::Pending,
+            "verified" => VerificationStatus::Verified,
+            "rejected" => VerificationStatus::Rejected,
+            _ => VerificationStatus::Unknown(String::from(status)),
+        }
+    }
+}
+
+impl fmt::Display for VerificationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerificationStatus::Unverified => write!(f, "unverified"),
+            VerificationStatus::Pending => write!(f, "pending"),
+            VerificationStatus::Verified => write!(f, "verified"),
+            VerificationStatus::Rejected => write!(f, "rejected"),
+            VerificationStatus::Unknown(ref s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for VerificationStatus {
+    fn to_json(&self) -> json::Json {
+        json::Json::String(format!("{}", self))
+    }
+}
+
 /// Struct that holds all the profile information for the user.
 #[derive(Clone, Debug)]
 pub struct Profile {
@@ -140,6 +363,28 @@ impl Profile {
     }
 }
 
+/// Profiles are identified by their user ID, so two profiles of the same user are equal even if
+/// some of their fields differ.
+impl PartialEq for Profile {
+    fn eq(&self, other: &Profile) -> bool {
+        self.user_id == other.user_id
+    }
+}
+
+impl Eq for Profile {}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (#{})", self.display_name, self.user_id)
+    }
+}
+
+impl Hash for Profile {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.user_id.hash(state);
+    }
+}
+
 #[cfg(feature = "json-types")]
 impl json::ToJson for Profile {
     fn to_json(&self) -> json::Json {
@@ -172,6 +417,43 @@ impl FromDTO<ProfileDTO> for Profile {
     }
 }
 
+/// Confirmable field of a user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UserField {
+    /// Email of the user.
+    Email,
+    /// First name of the user.
+    FirstName,
+    /// Last name of the user.
+    LastName,
+    /// Birthday of the user.
+    Birthday,
+    /// Phone of the user.
+    Phone,
+    /// Address of the user.
+    Address,
+}
+
+impl fmt::Display for UserField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UserField::Email => write!(f, "email"),
+            UserField::FirstName => write!(f, "first_name"),
+            UserField::LastName => write!(f, "last_name"),
+            UserField::Birthday => write!(f, "birthday"),
+            UserField::Phone => write!(f, "phone"),
+            UserField::Address => write!(f, "address"),
+        }
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for UserField {
+    fn to_json(&self) -> json::Json {
+        json::Json::String(format!("{}", self))
+    }
+}
+
 /// Struct that holds all personal information for the user.
 #[derive(Clone, Debug)]
 pub struct User {
@@ -302,6 +584,14 @@ impl User {
         self.pending_balance
     }
 
+    /// Gets the total balance of the user.
+    ///
+    /// This is the sum of the checking and cold balances. The pending balance is not included,
+    /// since it cannot be spent until the pending transactions are confirmed.
+    pub fn total_balance(&self) -> Amount {
+        self.checking_balance + self.cold_balance
+    }
+
     /// Gets the birthday of the user, if it has been set.
     pub fn get_birthday(&self) -> Option<NaiveDate> {
         match self.birthday {
@@ -310,6 +600,23 @@ impl User {
         }
     }
 
+    /// Gets the current age of the user, in years, if the birthday has been set.
+    pub fn age(&self) -> Option<u8> {
+        match self.birthday {
+            Some((b, _c)) => {
+                let age = age_at(b, UTC::now().naive_utc().date());
+                if age < 0 {
+                    Some(0)
+                } else if age > u8::max_value() as i32 {
+                    Some(u8::max_value())
+                } else {
+                    Some(age as u8)
+                }
+            }
+            None => None,
+        }
+    }
+
     /// Returns wether the birthday of the user has been confirmed or not.
     pub fn is_birthday_confirmed(&self) -> bool {
         match self.birthday {
@@ -358,6 +665,32 @@ impl User {
         }
     }
 
+    /// Gets the fields of the user that have not been confirmed yet.
+    ///
+    /// Fields that have not been set are also returned, since they can't be confirmed either.
+    pub fn unconfirmed_fields(&self) -> Vec<UserField> {
+        let mut fields = Vec::new();
+        if !self.is_email_confirmed() {
+            fields.push(UserField::Email);
+        }
+        if !self.is_first_name_confirmed() {
+            fields.push(UserField::FirstName);
+        }
+        if !self.is_last_name_confirmed() {
+            fields.push(UserField::LastName);
+        }
+        if !self.is_birthday_confirmed() {
+            fields.push(UserField::Birthday);
+        }
+        if !self.is_phone_confirmed() {
+            fields.push(UserField::Phone);
+        }
+        if !self.is_address_confirmed() {
+            fields.push(UserField::Address);
+        }
+        fields
+    }
+
     /// Gets the sybil score of the user.
     pub fn get_sybil_score(&self) -> i8 {
         self.sybil_score
@@ -394,6 +727,22 @@ impl User {
     }
 }
 
+/// Users are identified by their ID, so two fetches of the same user are equal even if some of
+/// their fields differ, such as the last activity time.
+impl PartialEq for User {
+    fn eq(&self, other: &User) -> bool {
+        self.user_id == other.user_id
+    }
+}
+
+impl Eq for User {}
+
+impl Hash for User {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.user_id.hash(state);
+    }
+}
+
 #[cfg(feature = "json-types")]
 impl json::ToJson for User {
     fn to_json(&self) -> json::Json {
@@ -539,6 +888,308 @@ impl FromDTO<UserDTO> for User {
     }
 }
 
+/// Kind of wallet of a user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WalletKind {
+    /// Checking wallet.
+    Checking,
+    /// Cold wallet.
+    Cold,
+}
+
+impl fmt::Display for WalletKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WalletKind::Checking => write!(f, "checking"),
+            WalletKind::Cold => write!(f, "cold"),
+        }
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for WalletKind {
+    fn to_json(&self) -> json::Json {
+        json::Json::String(format!("{}", self))
+    }
+}
+
+/// Wallet of a user.
+#[derive(Clone, Debug)]
+pub struct Wallet {
+    address: WalletAddress,
+    kind: WalletKind,
+    label: Option<String>,
+    balance: Amount,
+}
+
+impl Wallet {
+    /// Gets the address of the wallet.
+    pub fn get_address(&self) -> WalletAddress {
+        self.address
+    }
+
+    /// Gets the kind of the wallet.
+    pub fn get_kind(&self) -> WalletKind {
+        self.kind
+    }
+
+    /// Gets the label of the wallet, if the user has set one.
+    pub fn get_label(&self) -> Option<&str> {
+        match self.label {
+            Some(ref l) => Some(l),
+            None => None,
+        }
+    }
+
+    /// Gets the balance of the wallet.
+    pub fn get_balance(&self) -> Amount {
+        self.balance
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for Wallet {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("address"), self.address.to_json());
+        let _ = object.insert(String::from("kind"), self.kind.to_json());
+        if let Some(ref label) = self.label {
+            let _ = object.insert(String::from("label"), label.to_json());
+        }
+        let _ = object.insert(String::from("balance"), self.balance.to_json());
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<WalletDTO> for Wallet {
+    fn from_dto(dto: WalletDTO) -> StdResult<Wallet, FromDTOError> {
+        let kind = match dto.kind.as_str() {
+            "checking" => WalletKind::Checking,
+            "cold" => WalletKind::Cold,
+            _ => return Err(FromDTOError::new("the kind of the wallet is not valid")),
+        };
+        Ok(Wallet {
+            address: dto.address,
+            kind: kind,
+            label: dto.label,
+            balance: dto.balance,
+        })
+    }
+}
+
+/// Balances of a user.
+///
+/// This is a lightweight alternative to getting the whole `User` when only the balances are
+/// needed.
+#[derive(Clone, Copy, Debug)]
+pub struct Balances {
+    checking: Amount,
+    cold: Amount,
+    pending: Amount,
+}
+
+impl Balances {
+    /// Gets the checking balance of the user.
+    pub fn get_checking(&self) -> Amount {
+        self.checking
+    }
+
+    /// Gets the cold balance of the user.
+    pub fn get_cold(&self) -> Amount {
+        self.cold
+    }
+
+    /// Gets the pending balance of the user.
+    pub fn get_pending(&self) -> Amount {
+        self.pending
+    }
+
+    /// Gets the total balance of the user.
+    ///
+    /// As in `User::total_balance()`, the pending balance is not included.
+    pub fn total(&self) -> Amount {
+        self.checking + self.cold
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for Balances {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("checking"), self.checking.to_json());
+        let _ = object.insert(String::from("cold"), self.cold.to_json());
+        let _ = object.insert(String::from("pending"), self.pending.to_json());
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<BalancesDTO> for Balances {
+    fn from_dto(dto: BalancesDTO) -> StdResult<Balances, FromDTOError> {
+        Ok(Balances {
+            checking: dto.checking,
+            cold: dto.cold,
+            pending: dto.pending,
+        })
+    }
+}
+
+/// Notification preferences of a user.
+///
+/// Each category of notifications, both emails and pushes, can be enabled or disabled
+/// separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotificationPrefs {
+    transactions: bool,
+    friend_requests: bool,
+    marketing: bool,
+}
+
+impl NotificationPrefs {
+    /// Creates new notification preferences.
+    pub fn new(transactions: bool, friend_requests: bool, marketing: bool) -> NotificationPrefs {
+        NotificationPrefs {
+            transactions: transactions,
+            friend_requests: friend_requests,
+            marketing: marketing,
+        }
+    }
+
+    /// Returns wether the user is notified about transactions.
+    pub fn transactions(&self) -> bool {
+        self.transactions
+    }
+
+    /// Returns wether the user is notified about friend requests.
+    pub fn friend_requests(&self) -> bool {
+        self.friend_requests
+    }
+
+    /// Returns wether the user receives marketing notifications.
+    pub fn marketing(&self) -> bool {
+        self.marketing
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for NotificationPrefs {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("transactions"), self.transactions.to_json());
+        let _ = object.insert(String::from("friend_requests"), self.friend_requests.to_json());
+        let _ = object.insert(String::from("marketing"), self.marketing.to_json());
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<NotificationPrefsDTO> for NotificationPrefs {
+    fn from_dto(dto: NotificationPrefsDTO) -> StdResult<NotificationPrefs, FromDTOError> {
+        Ok(NotificationPrefs {
+            transactions: dto.transactions,
+            friend_requests: dto.friend_requests,
+            marketing: dto.marketing,
+        })
+    }
+}
+
+/// Aggregate statistics of the platform.
+#[derive(Clone, Copy, Debug)]
+pub struct PlatformStats {
+    total_users: u64,
+    total_transactions: u64,
+    total_volume: Amount,
+    daily_active_users: u64,
+}
+
+impl PlatformStats {
+    /// Gets the total number of users.
+    pub fn get_total_users(&self) -> u64 {
+        self.total_users
+    }
+
+    /// Gets the total number of transactions.
+    pub fn get_total_transactions(&self) -> u64 {
+        self.total_transactions
+    }
+
+    /// Gets the total amount transferred in all the transactions.
+    pub fn get_total_volume(&self) -> Amount {
+        self.total_volume
+    }
+
+    /// Gets the number of users active in the last day.
+    pub fn get_daily_active_users(&self) -> u64 {
+        self.daily_active_users
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for PlatformStats {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("total_users"), self.total_users.to_json());
+        let _ = object.insert(String::from("total_transactions"),
+                              self.total_transactions.to_json());
+        let _ = object.insert(String::from("total_volume"), self.total_volume.to_json());
+        let _ = object.insert(String::from("daily_active_users"),
+                              self.daily_active_users.to_json());
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<PlatformStatsDTO> for PlatformStats {
+    fn from_dto(dto: PlatformStatsDTO) -> StdResult<PlatformStats, FromDTOError> {
+        Ok(PlatformStats {
+            total_users: dto.total_users,
+            total_transactions: dto.total_transactions,
+            total_volume: dto.total_volume,
+            daily_active_users: dto.daily_active_users,
+        })
+    }
+}
+
+/// Reward of a user.
+#[derive(Clone, Copy, Debug)]
+pub struct Reward {
+    amount: Amount,
+    timestamp: DateTime<UTC>,
+}
+
+impl Reward {
+    /// Gets the amount of the reward.
+    pub fn get_amount(&self) -> Amount {
+        self.amount
+    }
+
+    /// Gets the time when the reward will be given.
+    pub fn get_timestamp(&self) -> DateTime<UTC> {
+        self.timestamp
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for Reward {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("amount"), self.amount.to_json());
+        let _ = object.insert(String::from("timestamp"), time_to_json(self.timestamp));
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<RewardDTO> for Reward {
+    fn from_dto(dto: RewardDTO) -> StdResult<Reward, FromDTOError> {
+        Ok(Reward {
+            amount: dto.amount,
+            timestamp: dto.timestamp,
+        })
+    }
+}
+
 /// The representation of a global credit transaction
 #[derive(Clone, Debug)]
 pub struct Transaction {
@@ -583,6 +1234,37 @@ impl Transaction {
     }
 }
 
+/// Two transactions are equal if they have the same timestamp and ID, consistently with their
+/// ordering.
+impl PartialEq for Transaction {
+    fn eq(&self, other: &Transaction) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Transaction {}
+
+impl Hash for Transaction {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.transaction_id.hash(state);
+    }
+}
+
+/// Transactions are ordered by their timestamp, and then by their ID.
+impl PartialOrd for Transaction {
+    fn partial_cmp(&self, other: &Transaction) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Transaction {
+    fn cmp(&self, other: &Transaction) -> Ordering {
+        self.timestamp
+            .cmp(&other.timestamp)
+            .then_with(|| self.transaction_id.cmp(&other.transaction_id))
+    }
+}
+
 #[cfg(feature = "json-types")]
 impl json::ToJson for Transaction {
     fn to_json(&self) -> json::Json {
@@ -613,6 +1295,78 @@ impl FromDTO<TransactionDTO> for Transaction {
     }
 }
 
+/// A transaction that has been generated but not yet confirmed.
+#[derive(Clone, Debug)]
+pub struct PendingTransaction {
+    /// The code of the transaction
+    code: String,
+    /// The id of the user sending the transaction
+    origin_id: u64,
+    /// The id of the user receiving the transaction
+    destination_id: u64,
+    /// The destination address of the transaction
+    destination_address: WalletAddress,
+    /// The amount of the transaction
+    amount: Amount,
+    /// The time when the transaction was generated
+    timestamp: DateTime<UTC>,
+}
+
+impl PendingTransaction {
+    /// Returns the code of the transaction
+    pub fn get_code(&self) -> &str {
+        &self.code
+    }
+    /// Returns the id of the user sending the transaction
+    pub fn get_origin_id(&self) -> u64 {
+        self.origin_id
+    }
+    /// Returns the id of the user receiving the transaction
+    pub fn get_destination_id(&self) -> u64 {
+        self.destination_id
+    }
+    /// Returns the wallet address receiving the transaction
+    pub fn get_destination_address(&self) -> &WalletAddress {
+        &self.destination_address
+    }
+    /// The amount of the transaction in global credits
+    pub fn get_amount(&self) -> Amount {
+        self.amount
+    }
+    /// The time when the transaction was generated
+    pub fn get_timestamp(&self) -> &DateTime<UTC> {
+        &self.timestamp
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for PendingTransaction {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("code"), self.code.to_json());
+        let _ = object.insert(String::from("origin_id"), self.origin_id.to_json());
+        let _ = object.insert(String::from("destination_id"), self.destination_id.to_json());
+        let _ = object.insert(String::from("destination_address"),
+                              self.destination_address.to_json());
+        let _ = object.insert(String::from("amount"), self.amount.to_json());
+        let _ = object.insert(String::from("timestamp"), time_to_json(self.timestamp));
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<PendingTransactionInfoDTO> for PendingTransaction {
+    fn from_dto(dto: PendingTransactionInfoDTO) -> StdResult<PendingTransaction, FromDTOError> {
+        Ok(PendingTransaction {
+            code: dto.code,
+            origin_id: dto.origin_id,
+            destination_id: dto.destination_id,
+            destination_address: dto.destination_address,
+            amount: dto.amount,
+            timestamp: dto.timestamp,
+        })
+    }
+}
+
 /// Pending friend request.
 #[derive(Clone, Debug)]
 pub struct PendingFriendRequest {
@@ -675,6 +1429,191 @@ impl FromDTO<PendingFriendRequestDTO> for PendingFriendRequest {
     }
 }
 
+/// Notification of an event for a user.
+#[derive(Clone, Debug)]
+pub enum Notification {
+    /// The user received a friend request.
+    FriendRequest {
+        /// ID of the notification.
+        id: u64,
+        /// Time when the notification was generated.
+        timestamp: DateTime<UTC>,
+        /// Whether the notification has been read.
+        read: bool,
+        /// Connection ID of the friend request.
+        connection_id: u64,
+        /// ID of the user that sent the friend request.
+        origin_id: u64,
+    },
+    /// The user received a transaction.
+    TransactionReceived {
+        /// ID of the notification.
+        id: u64,
+        /// Time when the notification was generated.
+        timestamp: DateTime<UTC>,
+        /// Whether the notification has been read.
+        read: bool,
+        /// ID of the transaction.
+        transaction_id: u64,
+        /// ID of the user that sent the transaction.
+        origin_id: u64,
+        /// Amount of the transaction.
+        amount: Amount,
+    },
+    /// The verification status of the user changed.
+    VerificationUpdate {
+        /// ID of the notification.
+        id: u64,
+        /// Time when the notification was generated.
+        timestamp: DateTime<UTC>,
+        /// Whether the notification has been read.
+        read: bool,
+        /// New verification status of the user.
+        status: VerificationStatus,
+    },
+    /// Notification of a kind not known by this client, as sent by the server.
+    Unknown {
+        /// ID of the notification.
+        id: u64,
+        /// Time when the notification was generated.
+        timestamp: DateTime<UTC>,
+        /// Whether the notification has been read.
+        read: bool,
+        /// Kind of the notification.
+        kind: String,
+    },
+}
+
+impl Notification {
+    /// Gets the ID of the notification.
+    pub fn get_id(&self) -> u64 {
+        match *self {
+            Notification::FriendRequest { id, .. } |
+            Notification::TransactionReceived { id, .. } |
+            Notification::VerificationUpdate { id, .. } |
+            Notification::Unknown { id, .. } => id,
+        }
+    }
+
+    /// Gets the time when the notification was generated.
+    pub fn get_timestamp(&self) -> DateTime<UTC> {
+        match *self {
+            Notification::FriendRequest { timestamp, .. } |
+            Notification::TransactionReceived { timestamp, .. } |
+            Notification::VerificationUpdate { timestamp, .. } |
+            Notification::Unknown { timestamp, .. } => timestamp,
+        }
+    }
+
+    /// Returns wether the notification has been read or not.
+    pub fn is_read(&self) -> bool {
+        match *self {
+            Notification::FriendRequest { read, .. } |
+            Notification::TransactionReceived { read, .. } |
+            Notification::VerificationUpdate { read, .. } |
+            Notification::Unknown { read, .. } => read,
+        }
+    }
+}
+
+#[cfg(feature = "json-types")]
+impl json::ToJson for Notification {
+    fn to_json(&self) -> json::Json {
+        let mut object = json::Object::new();
+        let _ = object.insert(String::from("id"), self.get_id().to_json());
+        let _ = object.insert(String::from("timestamp"), time_to_json(self.get_timestamp()));
+        let _ = object.insert(String::from("read"), self.is_read().to_json());
+        match *self {
+            Notification::FriendRequest { connection_id, origin_id, .. } => {
+                let _ = object.insert(String::from("kind"), "friend_request".to_json());
+                let _ = object.insert(String::from("connection_id"), connection_id.to_json());
+                let _ = object.insert(String::from("origin_id"), origin_id.to_json());
+            }
+            Notification::TransactionReceived { transaction_id, origin_id, amount, .. } => {
+                let _ = object.insert(String::from("kind"), "transaction".to_json());
+                let _ = object.insert(String::from("transaction_id"), transaction_id.to_json());
+                let _ = object.insert(String::from("origin_id"), origin_id.to_json());
+                let _ = object.insert(String::from("amount"), amount.to_json());
+            }
+            Notification::VerificationUpdate { ref status, .. } => {
+                let _ = object.insert(String::from("kind"), "verification".to_json());
+                let _ = object.insert(String::from("status"), status.to_json());
+            }
+            Notification::Unknown { ref kind, .. } => {
+                let _ = object.insert(String::from("kind"), kind.to_json());
+            }
+        }
+
+        json::Json::Object(object)
+    }
+}
+
+impl FromDTO<NotificationDTO> for Notification {
+    fn from_dto(dto: NotificationDTO) -> StdResult<Notification, FromDTOError> {
+        match dto.kind.as_str() {
+            "friend_request" => {
+                match (dto.connection_id, dto.user_id) {
+                    (Some(connection_id), Some(origin_id)) => {
+                        Ok(Notification::FriendRequest {
+                            id: dto.id,
+                            timestamp: dto.timestamp,
+                            read: dto.read,
+                            connection_id: connection_id,
+                            origin_id: origin_id,
+                        })
+                    }
+                    _ => {
+                        Err(FromDTOError::new("the friend request notification is missing the \
+                                               connection or the user"))
+                    }
+                }
+            }
+            "transaction" => {
+                match (dto.transaction_id, dto.user_id, dto.amount) {
+                    (Some(transaction_id), Some(origin_id), Some(amount)) => {
+                        Ok(Notification::TransactionReceived {
+                            id: dto.id,
+                            timestamp: dto.timestamp,
+                            read: dto.read,
+                            transaction_id: transaction_id,
+                            origin_id: origin_id,
+                            amount: amount,
+                        })
+                    }
+                    _ => {
+                        Err(FromDTOError::new("the transaction notification is missing the \
+                                               transaction, the user or the amount"))
+                    }
+                }
+            }
+            "verification" => {
+                match dto.status {
+                    Some(ref status) => {
+                        Ok(Notification::VerificationUpdate {
+                            id: dto.id,
+                            timestamp: dto.timestamp,
+                            read: dto.read,
+                            status: VerificationStatus::from(status.as_str()),
+                        })
+                    }
+                    None => {
+                        Err(FromDTOError::new("the verification notification is missing the \
+                                               status"))
+                    }
+                }
+            }
+            _ => {
+                Ok(Notification::Unknown {
+                    id: dto.id,
+                    timestamp: dto.timestamp,
+                    read: dto.read,
+                    kind: dto.kind.clone(),
+                })
+            }
+        }
+    }
+}
+
 #[cfg(feature = "json-types")]
 fn time_to_json(time: DateTime<UTC>) -> json::Json {
     use chrono::{Timelike, Datelike};
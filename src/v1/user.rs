@@ -1,227 +1,389 @@
-use std::io::Read;
+use std::thread;
+use std::io::Write;
+use std::time::{Duration, Instant};
 
 use hyper::method::Method;
 use hyper::header::{Headers, Authorization};
+use chrono::{NaiveDate, DateTime, UTC, Timelike};
 use rustc_serialize::json;
-use hyper::status::StatusCode;
-use chrono::NaiveDate;
-use utils::Address;
+use utils::{Address, WalletAddress};
 use dto::{FromDTO, UserDTO, ProfileDTO, AuthenticationCodeDTO, ResponseDTO, UpdateUserDTO,
           SearchUserDTO};
 
-use super::{Client, VoidDTO};
+use super::{Client, VoidDTO, MAX_AGE, age_at, encode_path_segment};
+use super::dtos::{AuthenticatorEnrollmentDTO, ClearUserFieldsDTO, ImageDTO,
+                  TrustScoreSampleDTO, FilteredSearchUserDTO, WalletDTO,
+                  WalletAddressDTO, DeleteAccountDTO, BalancesDTO, BulkSetEnabledDTO,
+                  BatchResultDTO, PlatformStatsDTO, NotificationPrefsDTO};
 use error::{Result, Error};
-use super::types::{User, Profile};
+use super::types::{User, Profile, AuthenticatorEnrollment, VerificationStatus, ApiMessage,
+                   Wallet, Balances, PlatformStats, NotificationPrefs};
 use super::oauth::AccessToken;
-use hyper::client::response::Response;
+use super::transport::RawResponse;
 /// User methods for the client.
 ///
 /// This are the user getters, setters and creators for the client.
 impl Client {
     /// Resends the email confirmation
-    pub fn resend_email_confirmation(&self, access_token: &AccessToken) -> Result<(ResponseDTO)> {
-        if access_token.get_user_id().is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}resend_email_confirmation", self.url),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            match response.status {
-                StatusCode::Ok => {
-                    let res: ResponseDTO = json::decode(&response_str)?;
-                    Ok(res)
-                }
-                _ => {
-                    Err(Error::Forbidden(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
-        }
+    ///
+    /// If the confirmation was resent for the same user in the last
+    /// [`RESEND_COOLDOWN_SECS`](constant.RESEND_COOLDOWN_SECS.html) seconds, an
+    /// `Error::RateLimited` will be returned without contacting the server.
+    pub fn resend_email_confirmation(&self, access_token: &AccessToken) -> Result<ApiMessage> {
+        self.require_user(access_token)?;
+        self.check_resend("resend_email_confirmation", access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}resend_email_confirmation", self.url),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.record_resend("resend_email_confirmation", access_token);
+        self.decode_message(&mut response)
+    }
+
+    /// Resends the phone verification SMS code
+    ///
+    /// As with `resend_email_confirmation()`, resends for the same user are limited to one every
+    /// [`RESEND_COOLDOWN_SECS`](constant.RESEND_COOLDOWN_SECS.html) seconds.
+    pub fn resend_phone_verification(&self, access_token: &AccessToken) -> Result<ApiMessage> {
+        self.require_user(access_token)?;
+        self.check_resend("resend_phone_verification", access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}resend_phone_verification", self.url),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.record_resend("resend_phone_verification", access_token);
+        self.decode_message(&mut response)
+    }
+
+    /// Confirms the phone of the user with the SMS code sent to it
+    pub fn confirm_phone(&self, access_token: &AccessToken, code: u32) -> Result<()> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = AuthenticationCodeDTO { code: code };
+        let _ = self.send_request(Method::Post,
+                          format!("{}confirm_phone", self.url),
+                          headers,
+                          Some(&dto))?;
+        Ok(())
+    }
+
+    /// Confirms the address of the user with the key sent to it
+    pub fn confirm_address<S: AsRef<str>>(&self,
+                                          access_token: &AccessToken,
+                                          key: S)
+                                          -> Result<()> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let url = format!("{}confirm_address/{}", self.url, encode_path_segment(key.as_ref()));
+        let _ = self.send_request(Method::Post,
+                          url,
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(())
     }
 
     /// Resends the unsubscribe email confirmation
-    pub fn unsubscribe_email_confirmation(&self, access_token: &AccessToken) -> Result<(ResponseDTO)> {
-        if access_token.get_user_id().is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}unsubscribe_email_confirmation", self.url),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            match response.status {
-                StatusCode::Ok => {
-                    let res: ResponseDTO = json::decode(&response_str)?;
-                    Ok(res)
-                }
-                _ => {
-                    Err(Error::Forbidden(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
-        }
+    pub fn unsubscribe_email_confirmation(&self, access_token: &AccessToken) -> Result<ApiMessage> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}unsubscribe_email_confirmation", self.url),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_message(&mut response)
     }
 
     /// Get the unlogged user profile
     pub fn get_unlogged_user(&self, access_token: &AccessToken, user_id: u64) -> Result<User> {
-        let logged_user_id = access_token.get_user_id();
-        if logged_user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}get_unlogged_user/{}", self.url, user_id),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            Ok(User::from_dto(json::decode::<UserDTO>(&response_str)?)?)
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
-        }
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}get_unlogged_user/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<UserDTO, User>(&mut response)
     }
-	
-	/// Get the  user profile by name
+
+    /// Gets the user with the given username
     pub fn get_user_by_name(&self, access_token: &AccessToken, user_name: &String) -> Result<User> {
-        let logged_user_id = access_token.get_user_id();
-        if logged_user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}get_user_from_username/{}", self.url, user_name),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            Ok(User::from_dto(json::decode::<UserDTO>(&response_str)?)?)
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
-        }
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}get_user_from_username/{}", self.url, user_name),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<UserDTO, User>(&mut response)
     }
 
     /// Get the user
     pub fn get_user(&self, access_token: &AccessToken, user_id: u64) -> Result<User> {
-        if (access_token.is_user(user_id) || access_token.is_admin()) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}user/{}", self.url, user_id),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            Ok(User::from_dto(json::decode::<UserDTO>(&response_str)?)?)
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-                                               token, and in the case of a user token, the ID \
-                                               in the token must match the given ID")))
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}user/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<UserDTO, User>(&mut response)
+    }
+
+    /// Gets the public profile of the given user
+    ///
+    /// It accepts both public and user tokens, and it's much lighter than getting the whole user.
+    pub fn get_profile(&self, access_token: &AccessToken, user_id: u64) -> Result<Profile> {
+        self.require_public_or_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let body = self.send_cached_get(format!("{}profile/{}", self.url, user_id), headers)?;
+        self.decode_dto_body::<ProfileDTO, Profile>(&body)
+    }
+
+    /// Gets the profiles of the users with the highest trust score, up to the given limit
+    ///
+    /// The profiles are ordered by their trust score, from highest to lowest. It accepts both
+    /// public and user tokens.
+    pub fn get_top_users(&self, access_token: &AccessToken, limit: u64) -> Result<Vec<Profile>> {
+        self.require_public_or_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}top_users?limit={}", self.url, limit),
+                          headers,
+                          None::<&VoidDTO>)?;
+        let mut profiles = self.decode_dto_list::<ProfileDTO, Profile>(&mut response)?;
+        profiles.sort_by(|a, b| b.get_trust_score().cmp(&a.get_trust_score()));
+        Ok(profiles)
+    }
+
+    /// Gets the profile of the user that referred the given user, if any
+    pub fn get_referrer(&self,
+                        access_token: &AccessToken,
+                        user_id: u64)
+                        -> Result<Option<Profile>> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}referrer/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        match self.decode_optional_response::<ProfileDTO>(&mut response)? {
+            Some(dto) => Ok(Some(Profile::from_dto(dto)?)),
+            None => Ok(None),
         }
     }
 
+    /// Gets the profiles of the users referred by the given user
+    pub fn get_referrals(&self, access_token: &AccessToken, user_id: u64) -> Result<Vec<Profile>> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}referrals/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<ProfileDTO, Profile>(&mut response)
+    }
+
     /// Gets the logged in users info
     pub fn get_me(&self, access_token: &AccessToken) -> Result<User> {
         let user_id = access_token.get_user_id();
-        if user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}user/{}", self.url, user_id.unwrap()),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-			println!("response in api-rs{:?}",response_str);
-			// let user = User::from_dto(json::decode::<UserDTO>(&response_str)?).unwrap();
-			// let waleet_address=user.wallet_addresses();
-                                // // .map(|a| format!("{}", a))
-                                // // .collect::<Vec<_>>();
-                               
-			// println!("wallet_addresses in api-rs{:?}",waleet_address);
-           Ok(User::from_dto(json::decode::<UserDTO>(&response_str)?)?)
-		   //Ok(response)
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
-        }
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let body = self.send_cached_get(format!("{}user/{}", self.url, user_id.unwrap()), headers)?;
+        self.decode_dto_body::<UserDTO, User>(&body)
     }
 
     /// Gets all users.
     pub fn get_all_users(&self, access_token: &AccessToken) -> Result<Vec<User>> {
-        if access_token.is_admin() && !access_token.has_expired() {
+        self.require_admin(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}all_users", self.url),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<UserDTO, User>(&mut response)
+    }
+
+    /// Writes all the users to the given writer, one JSON object per line, and returns the number
+    /// of users written
+    ///
+    /// Unlike `get_all_users()`, the users are fetched in pages of `USER_PAGE_SIZE` users and
+    /// written as they arrive, so the whole list is never kept in memory. This makes it suitable
+    /// for exporting the users to a file. An admin token is required.
+    ///
+    /// The server must support the `from` and `limit` parameters of the `all_users` endpoint. Users
+    /// with an ID lower than the requested one are never written, and the export stops as soon as
+    /// a page makes no progress, so a server ignoring them can't produce duplicates or an endless
+    /// loop.
+    pub fn stream_all_users<W: Write>(&self,
+                                      access_token: &AccessToken,
+                                      out: &mut W)
+                                      -> Result<u64> {
+        self.require_admin(access_token)?;
+        let mut count = 0;
+        let mut next_id = 0;
+        loop {
             let mut headers = Headers::new();
             headers.set(Authorization(access_token.get_token()));
             let mut response = self.send_request(Method::Get,
-                              format!("{}all_users", self.url),
+                              format!("{}all_users?from={}&limit={}",
+                                      self.url,
+                                      next_id,
+                                      USER_PAGE_SIZE),
                               headers,
                               None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let dto_users: Vec<UserDTO> = json::decode(&response_str)?;
-            Ok(dto_users.into_iter()
-                .filter_map(|u| match User::from_dto(u) {
-                    Ok(u) => Some(u),
-                    Err(_) => None,
-                })
-                .collect())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin token")))
+            let page: Vec<UserDTO> = self.decode_response(&mut response)?;
+            for user in page.iter().filter(|u| u.user_id >= next_id) {
+                writeln!(out, "{}", json::encode(user)?)?;
+                count += 1;
+            }
+            match page.iter().map(|u| u.user_id).max() {
+                Some(last_id) if page.len() >= USER_PAGE_SIZE && last_id >= next_id => {
+                    next_id = last_id + 1
+                }
+                _ => return Ok(count),
+            }
         }
     }
 
+    /// Gets the aggregate statistics of the platform, such as the total number of users or the
+    /// total transaction volume
+    ///
+    /// They are computed by the server, so there is no need to page through all the users or
+    /// transactions. An admin token is required.
+    pub fn get_platform_stats(&self, access_token: &AccessToken) -> Result<PlatformStats> {
+        self.require_admin(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}platform_stats", self.url),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<PlatformStatsDTO, PlatformStats>(&mut response)
+    }
+
     /// Deletes the given user.
     pub fn delete_user(&self, access_token: &AccessToken, user_id: u64) -> Result<()> {
-        if access_token.is_admin() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let _ = self.send_request(Method::Delete,
-                              format!("{}user/{}", self.url, user_id),
-                              headers,
-                              None::<&VoidDTO>)?;
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin token")))
+        self.require_admin(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let _ = self.send_request(Method::Delete,
+                          format!("{}user/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(())
+    }
+
+    /// Enables or disables the given users, in a single request
+    ///
+    /// The result for each user is returned along with its ID, in the same order, so that the
+    /// failure of some of them doesn't fail the whole batch. An admin token is required.
+    pub fn bulk_set_enabled(&self,
+                            access_token: &AccessToken,
+                            user_ids: &[u64],
+                            enabled: bool)
+                            -> Result<Vec<(u64, Result<()>)>> {
+        self.require_admin(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = BulkSetEnabledDTO {
+            user_ids: user_ids.to_vec(),
+            enabled: enabled,
+        };
+        let mut response = self.send_request(Method::Post,
+                          format!("{}bulk_set_enabled", self.url),
+                          headers,
+                          Some(&dto))?;
+        let results: Vec<BatchResultDTO> = self.decode_response(&mut response)?;
+        if results.len() != user_ids.len() {
+            return Err(Error::Server {
+                code: None,
+                message: String::from("the server did not return a result for each user"),
+            });
         }
+        Ok(user_ids.iter()
+            .zip(results)
+            .map(|(&id, r)| if r.success {
+                (id, Ok(()))
+            } else {
+                (id, Err(Error::BadRequest(r.message)))
+            })
+            .collect())
+    }
+
+    /// Deletes the account of the logged in user
+    ///
+    /// The user must confirm the deletion with their password. Unlike `delete_user()`, this does
+    /// not require an admin token.
+    pub fn delete_me<P: Into<String>>(&self,
+                                      access_token: &AccessToken,
+                                      password: P)
+                                      -> Result<()> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = DeleteAccountDTO { password: password.into() };
+        let _ = self.send_request(Method::Post,
+                          format!("{}delete_me", self.url),
+                          headers,
+                          Some(&dto))?;
+        Ok(())
+    }
+
+    /// Exports all the data held about the given user, as raw JSON
+    ///
+    /// The export includes the profile, transactions and connections of the user, and can be used
+    /// to fulfill data access requests.
+    pub fn export_user_data(&self, access_token: &AccessToken, user_id: u64) -> Result<String> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}export_user_data/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.read_body(&mut response)
     }
 
     // TODO update user
 
-    /// Generates a new authenticator code, and returns the URL.
-   pub fn generate_authenticator_code(&self, access_token: &AccessToken) -> Result<String> {
-        if access_token.get_user_id().is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}generate_authenticator_code", self.url),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-			println!("Success\n");
-            Ok(json::decode::<ResponseDTO>(&response_str)?.message)
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
-        }
+    /// Generates a new authenticator code, and returns its secret and `otpauth://` URL.
+    pub fn generate_authenticator_code(&self,
+                                       access_token: &AccessToken)
+                                       -> Result<AuthenticatorEnrollment> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}generate_authenticator_code", self.url),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<AuthenticatorEnrollmentDTO, AuthenticatorEnrollment>(&mut response)
     }
 
     /// Authenticates the user with 2FA
     pub fn authenticate(&self, access_token: &AccessToken, code: u32) -> Result<()> {
-        if access_token.get_user_id().is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = AuthenticationCodeDTO { code: code };
-            let _ = self.send_request(Method::Post,
-                              format!("{}authenticate", self.url),
-                              headers,
-                              Some(&dto))?;
-            Ok(())
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
-        }
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = AuthenticationCodeDTO { code: code };
+        let _ = self.send_request(Method::Post,
+                          format!("{}authenticate", self.url),
+                          headers,
+                          Some(&dto))?;
+        Ok(())
     }
 
 
@@ -230,43 +392,28 @@ impl Client {
                                          access_token: &AccessToken,
                                          user_id: u64,
                                          username: U)
-                                         -> Result<Response> {
-        if (access_token.is_user(user_id) || access_token.is_admin()) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = UpdateUserDTO {
-                new_username: Some(username.into()),
-                new_email: None,
-                new_first: None,
-                new_last: None,
-                old_password: None,
-                new_password: None,
-                new_phone: None,
-                new_birthday: None,
-                new_image: None,
-                new_address: None,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}update_user/{}", self.url, user_id),
-                              headers,
-                              Some(&dto))?;
-            
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(response)
-                }
-                _ => {
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Err(Error::Forbidden(response_str))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-                                               token, and in the case of a user token, the ID \
-                                               in the token must match the given ID")))
-        }
+                                         -> Result<RawResponse> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateUserDTO {
+            new_username: Some(username.into()),
+            new_email: None,
+            new_first: None,
+            new_last: None,
+            old_password: None,
+            new_password: None,
+            new_phone: None,
+            new_birthday: None,
+            new_image: None,
+            new_address: None,
+        };
+        let response = self.send_request(Method::Post,
+                          format!("{}update_user/{}", self.url, user_id),
+                          headers,
+                          Some(&dto))?;
+        
+        Ok(response)
     }
 
     /// Sets the users phone
@@ -274,89 +421,77 @@ impl Client {
                                       access_token: &AccessToken,
                                       user_id: u64,
                                       phone: P)
-                                      -> Result<Response> {
-        if (access_token.is_user(user_id) || access_token.is_admin()) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = UpdateUserDTO {
-                new_username: None,
-                new_email: None,
-                new_first: None,
-                new_last: None,
-                old_password: None,
-                new_password: None,
-                new_phone: Some(phone.into()),
-                new_birthday: None,
-                new_image: None,
-                new_address: None,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}update_user/{}", self.url, user_id),
-                              headers,
-                              Some(&dto))?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(response)
-                }
-                _ => {
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Err(Error::Forbidden(response_str))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-                                               token, and in the case of a user token, the ID \
-                                               in the token must match the given ID")))
+                                      -> Result<RawResponse> {
+        let phone = phone.into();
+        if !is_valid_phone(&phone) {
+            return Err(Error::BadRequest(format!("the phone `{}` is not a valid phone", phone)));
         }
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateUserDTO {
+            new_username: None,
+            new_email: None,
+            new_first: None,
+            new_last: None,
+            old_password: None,
+            new_password: None,
+            new_phone: Some(phone),
+            new_birthday: None,
+            new_image: None,
+            new_address: None,
+        };
+        let response = self.send_request(Method::Post,
+                          format!("{}update_user/{}", self.url, user_id),
+                          headers,
+                          Some(&dto))?;
+        Ok(response)
     }
 
     /// Sets the users birthday
+    ///
+    /// Birthdays in the future, of users younger than the minimum age of the client, or of users
+    /// older than [`MAX_AGE`](constant.MAX_AGE.html) will be rejected with an `Error::BadRequest`
+    /// without sending them to the server. The minimum age can be configured with
+    /// `set_min_age()`.
     pub fn set_birthday(&self,
                         access_token: &AccessToken,
                         user_id: u64,
                         birthday: NaiveDate)
-                        -> Result<Response> {
-        if (access_token.is_user(user_id) || access_token.is_admin()) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = UpdateUserDTO {
-                new_username: None,
-                new_email: None,
-                new_first: None,
-                new_last: None,
-                old_password: None,
-                new_password: None,
-                new_phone: None,
-                new_birthday: Some(birthday),
-                new_image: None,
-                new_address: None,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}update_user/{}", self.url, user_id),
-                              headers,
-                              Some(&dto))?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(response)
-                }
-                _ => {
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Err(Error::Forbidden(response_str))
-                }
-            }
-
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-                              token, and in the case of a user token, the ID \
-                              in the token must match the given ID")))
-            // Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-            //                                    token, and in the case of a user token, the ID \
-            //                                    in the token must match the given ID")))
+                        -> Result<RawResponse> {
+        let today = UTC::now().naive_utc().date();
+        if birthday > today {
+            return Err(Error::BadRequest(String::from("the birthday cannot be in the future")));
+        }
+        let age = age_at(birthday, today);
+        if age < self.min_age as i32 {
+            return Err(Error::BadRequest(format!("the user must be at least {} years old",
+                                                 self.min_age)));
         }
+        if age > MAX_AGE as i32 {
+            return Err(Error::BadRequest(String::from("the birthday is not a plausible date")));
+        }
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateUserDTO {
+            new_username: None,
+            new_email: None,
+            new_first: None,
+            new_last: None,
+            old_password: None,
+            new_password: None,
+            new_phone: None,
+            new_birthday: Some(birthday),
+            new_image: None,
+            new_address: None,
+        };
+        let response = self.send_request(Method::Post,
+                          format!("{}update_user/{}", self.url, user_id),
+                          headers,
+                          Some(&dto))?;
+        Ok(response)
+
     }
 
     /// Sets the users first and last name
@@ -365,42 +500,27 @@ impl Client {
                                                       user_id: u64,
                                                       first: F,
                                                       last: L)
-                                                      -> Result<(Response)> {
-        if (access_token.is_user(user_id) || access_token.is_admin()) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = UpdateUserDTO {
-                new_username: None,
-                new_email: None,
-                new_first: Some(first.into()),
-                new_last: Some(last.into()),
-                old_password: None,
-                new_password: None,
-                new_phone: None,
-                new_birthday: None,
-                new_image: None,
-                new_address: None,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}update_user/{}", self.url, user_id),
-                              headers,
-                               Some(&dto))?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(response)
-                }
-                _ => {
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Err(Error::Forbidden(response_str))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-                                               token, and in the case of a user token, the ID \
-                                               in the token must match the given ID")))
-        }
+                                                      -> Result<RawResponse> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateUserDTO {
+            new_username: None,
+            new_email: None,
+            new_first: Some(first.into()),
+            new_last: Some(last.into()),
+            old_password: None,
+            new_password: None,
+            new_phone: None,
+            new_birthday: None,
+            new_image: None,
+            new_address: None,
+        };
+        let response = self.send_request(Method::Post,
+                          format!("{}update_user/{}", self.url, user_id),
+                          headers,
+                           Some(&dto))?;
+        Ok(response)
     }
 
     /// Sets the users email
@@ -408,42 +528,31 @@ impl Client {
                                       access_token: &AccessToken,
                                       user_id: u64,
                                       email: E)
-                                      -> Result<(Response)> {
-        if (access_token.is_user(user_id) || access_token.is_admin()) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = UpdateUserDTO {
-                new_username: None,
-                new_email: Some(email.into()),
-                new_first: None,
-                new_last: None,
-                old_password: None,
-                new_password: None,
-                new_phone: None,
-                new_birthday: None,
-                new_image: None,
-                new_address: None,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}update_user/{}", self.url, user_id),
-                              headers,
-                              Some(&dto))?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(response)
-                }
-                _ => {
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Err(Error::Forbidden(response_str))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-                                               token, and in the case of a user token, the ID \
-                                               in the token must match the given ID")))
+                                      -> Result<RawResponse> {
+        let email = email.into();
+        if !is_valid_email(&email) {
+            return Err(Error::BadRequest(format!("the email `{}` is not a valid email", email)));
         }
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateUserDTO {
+            new_username: None,
+            new_email: Some(email),
+            new_first: None,
+            new_last: None,
+            old_password: None,
+            new_password: None,
+            new_phone: None,
+            new_birthday: None,
+            new_image: None,
+            new_address: None,
+        };
+        let response = self.send_request(Method::Post,
+                          format!("{}update_user/{}", self.url, user_id),
+                          headers,
+                          Some(&dto))?;
+        Ok(response)
     }
 
     /// Sets the users profile picture to the given URL
@@ -451,42 +560,70 @@ impl Client {
                                       access_token: &AccessToken,
                                       user_id: u64,
                                       image_url: I)
-                                      -> Result<(Response)> {
-        if (access_token.is_user(user_id) || access_token.is_admin()) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = UpdateUserDTO {
-                new_username: None,
-                new_email: None,
-                new_first: None,
-                new_last: None,
-                old_password: None,
-                new_password: None,
-                new_phone: None,
-                new_birthday: None,
-                new_image: Some(image_url.into()),
-                new_address: None,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}update_user/{}", self.url, user_id),
-                              headers,
-                              Some(&dto))?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(response)
-                }
-                _ => {
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Err(Error::Forbidden(response_str))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-                                               token, and in the case of a user token, the ID \
-                                               in the token must match the given ID")))
+                                      -> Result<RawResponse> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateUserDTO {
+            new_username: None,
+            new_email: None,
+            new_first: None,
+            new_last: None,
+            old_password: None,
+            new_password: None,
+            new_phone: None,
+            new_birthday: None,
+            new_image: Some(image_url.into()),
+            new_address: None,
+        };
+        let response = self.send_request(Method::Post,
+                          format!("{}update_user/{}", self.url, user_id),
+                          headers,
+                          Some(&dto))?;
+        Ok(response)
+    }
+
+    /// Uploads the given image as the profile picture of the user, and returns its URL
+    ///
+    /// The image is sent as a `multipart/form-data` request, with the given content type, that
+    /// must be an `image/*` type.
+    pub fn upload_user_image(&self,
+                             access_token: &AccessToken,
+                             user_id: u64,
+                             bytes: &[u8],
+                             content_type: &str)
+                             -> Result<String> {
+        if !content_type.starts_with("image/") || content_type.contains('\r') ||
+           content_type.contains('\n') {
+            return Err(Error::BadRequest(format!("the content type `{}` is not a valid image \
+                                                  content type",
+                                                 content_type)));
         }
+        self.require_user_or_admin(access_token, user_id)?;
+        let now = UTC::now();
+        let boundary = format!("------------------------fractal{}{}",
+                               now.timestamp(),
+                               now.nanosecond());
+        let mut body = Vec::with_capacity(bytes.len() + 256);
+        body.extend_from_slice(format!("--{}\r\nContent-Disposition: form-data; \
+                                        name=\"image\"; filename=\"image\"\r\n\
+                                        Content-Type: {}\r\n\r\n",
+                                       boundary,
+                                       content_type)
+            .as_bytes());
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        headers.set_raw("Content-Type",
+                        vec![format!("multipart/form-data; boundary={}", boundary)
+                                 .into_bytes()]);
+        let mut response = self.send_raw_request(Method::Post,
+                          format!("{}upload_image/{}", self.url, user_id),
+                          headers,
+                          Some(&body[..]))?;
+        Ok(self.decode_response::<ImageDTO>(&mut response)?.image_url)
     }
 
     /// Sets the users address
@@ -494,42 +631,54 @@ impl Client {
                        access_token: &AccessToken,
                        user_id: u64,
                        address: Address)
-                       -> Result<(Response)> {
-        if (access_token.is_user(user_id) || access_token.is_admin()) &&
-           !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = UpdateUserDTO {
-                new_username: None,
-                new_email: None,
-                new_first: None,
-                new_last: None,
-                old_password: None,
-                new_password: None,
-                new_phone: None,
-                new_birthday: None,
-                new_image: None,
-                new_address: Some(address),
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}update_user/{}", self.url, user_id),
-                              headers,
-                              Some(&dto))?;
-           match response.status {
-                StatusCode::Ok => {
-                    Ok(response)
-                }
-                _ => {
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Err(Error::Forbidden(response_str))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired admin or user \
-                                               token, and in the case of a user token, the ID \
-                                               in the token must match the given ID")))
-        }
+                       -> Result<RawResponse> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateUserDTO {
+            new_username: None,
+            new_email: None,
+            new_first: None,
+            new_last: None,
+            old_password: None,
+            new_password: None,
+            new_phone: None,
+            new_birthday: None,
+            new_image: None,
+            new_address: Some(address),
+        };
+        let response = self.send_request(Method::Post,
+                          format!("{}update_user/{}", self.url, user_id),
+                          headers,
+                          Some(&dto))?;
+        Ok(response)
+    }
+
+    /// Removes the phone of the user
+    pub fn clear_phone(&self, access_token: &AccessToken, user_id: u64) -> Result<()> {
+        self.clear_user_field(access_token, user_id, "phone")
+    }
+
+    /// Removes the profile picture of the user
+    pub fn clear_image(&self, access_token: &AccessToken, user_id: u64) -> Result<()> {
+        self.clear_user_field(access_token, user_id, "image")
+    }
+
+    /// Removes the address of the user
+    pub fn clear_address(&self, access_token: &AccessToken, user_id: u64) -> Result<()> {
+        self.clear_user_field(access_token, user_id, "address")
+    }
+
+    fn clear_user_field(&self, access_token: &AccessToken, user_id: u64, field: &str) -> Result<()> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = ClearUserFieldsDTO { fields: vec![String::from(field)] };
+        let _ = self.send_request(Method::Post,
+                          format!("{}clear_user_fields/{}", self.url, user_id),
+                          headers,
+                          Some(&dto))?;
+        Ok(())
     }
 
     /// Sets the user password
@@ -537,40 +686,130 @@ impl Client {
                                                           access_token: &AccessToken,
                                                           old_password: O,
                                                           new_password: N)
-                                                          -> Result<(Response)> {
+                                                          -> Result<RawResponse> {
         let user_id = access_token.get_user_id();
-        if user_id.is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let dto = UpdateUserDTO {
-                new_username: None,
-                new_email: None,
-                new_first: None,
-                new_last: None,
-                old_password: Some(old_password.into()),
-                new_password: Some(new_password.into()),
-                new_phone: None,
-                new_birthday: None,
-                new_image: None,
-                new_address: None,
-            };
-            let mut response = self.send_request(Method::Post,
-                              format!("{}update_user/{}", self.url, user_id.unwrap()),
-                              headers,
-                              Some(&dto))?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(response)
-                }
-                _ => {
-                    let mut response_str = String::new();
-                    let _ = response.read_to_string(&mut response_str)?;
-                    Err(Error::Forbidden(response_str))
-                }
-            }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
-        }
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = UpdateUserDTO {
+            new_username: None,
+            new_email: None,
+            new_first: None,
+            new_last: None,
+            old_password: Some(old_password.into()),
+            new_password: Some(new_password.into()),
+            new_phone: None,
+            new_birthday: None,
+            new_image: None,
+            new_address: None,
+        };
+        let response = self.send_request(Method::Post,
+                          format!("{}update_user/{}", self.url, user_id.unwrap()),
+                          headers,
+                          Some(&dto))?;
+        Ok(response)
+    }
+
+    /// Gets the history of the trust score of the given user, as timestamped samples
+    pub fn get_trust_score_history(&self,
+                                   access_token: &AccessToken,
+                                   user_id: u64)
+                                   -> Result<Vec<(DateTime<UTC>, i8)>> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}trust_score_history/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        let samples: Vec<TrustScoreSampleDTO> = self.decode_response(&mut response)?;
+        Ok(samples.into_iter()
+            .map(|s| (s.timestamp, s.trust_score))
+            .collect())
+    }
+
+    /// Gets the wallets of the given user, with their kind, label and balance
+    pub fn get_wallets(&self, access_token: &AccessToken, user_id: u64) -> Result<Vec<Wallet>> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}wallets/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto_list::<WalletDTO, Wallet>(&mut response)
+    }
+
+    /// Gets the checking, cold and pending balances of the given user
+    ///
+    /// This is much lighter than `get_me()`, so it should be preferred for frequently refreshed
+    /// balances.
+    pub fn get_balances(&self, access_token: &AccessToken, user_id: u64) -> Result<Balances> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}balances/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<BalancesDTO, Balances>(&mut response)
+    }
+
+    /// Gets the notification preferences of the given user
+    pub fn get_notification_prefs(&self,
+                                  access_token: &AccessToken,
+                                  user_id: u64)
+                                  -> Result<NotificationPrefs> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}notification_prefs/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        self.decode_dto::<NotificationPrefsDTO, NotificationPrefs>(&mut response)
+    }
+
+    /// Sets the notification preferences of the given user
+    ///
+    /// Unlike `unsubscribe_email_confirmation()`, this allows enabling or disabling each category
+    /// of notifications separately.
+    pub fn set_notification_prefs(&self,
+                                  access_token: &AccessToken,
+                                  user_id: u64,
+                                  prefs: NotificationPrefs)
+                                  -> Result<()> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let dto = NotificationPrefsDTO {
+            transactions: prefs.transactions(),
+            friend_requests: prefs.friend_requests(),
+            marketing: prefs.marketing(),
+        };
+        let _ = self.send_request(Method::Post,
+                          format!("{}notification_prefs/{}", self.url, user_id),
+                          headers,
+                          Some(&dto))?;
+        Ok(())
+    }
+
+    /// Generates a new wallet address for the given user, and returns it
+    ///
+    /// The previous addresses of the user will still be valid, so this can be used to receive
+    /// each payment in a different address.
+    pub fn generate_wallet_address(&self,
+                                   access_token: &AccessToken,
+                                   user_id: u64)
+                                   -> Result<WalletAddress> {
+        self.require_user_or_admin(access_token, user_id)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Post,
+                          format!("{}generate_wallet_address/{}", self.url, user_id),
+                          headers,
+                          None::<&VoidDTO>)?;
+        Ok(self.decode_response::<WalletAddressDTO>(&mut response)?.address)
     }
 
     /// Searches users doing a random search with the given string. It will try to find the string
@@ -611,68 +850,224 @@ impl Client {
                               format!("{}search_user", self.url),
                               headers,
                               Some(&dto))?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let dto_users: Vec<ProfileDTO> = json::decode(&response_str)?;
-            Ok(dto_users.into_iter()
-                .filter_map(|u| match Profile::from_dto(u) {
-                    Ok(u) => Some(u),
-                    Err(_) => None,
-                })
-                .collect())
+            self.decode_dto_list::<ProfileDTO, Profile>(&mut response)
         } else {
             Err(Error::Forbidden(String::from("the token must be an unexpired public or user \
                                                token")))
         }
     }
     
-    ///initiates user verification
+    /// Searches users with the given search query
+    ///
+    /// Unlike `search_user_random()`, the query can filter the users by their scores. Including
+    /// the current user or its friends requires a user scoped token.
+    pub fn search_user(&self,
+                       access_token: &AccessToken,
+                       search: &UserSearch)
+                       -> Result<Vec<Profile>> {
+        let user_id = access_token.get_user_id();
+        if (access_token.is_public() || user_id.is_some() || access_token.is_admin()) &&
+           !access_token.has_expired() {
+            if (search.include_me || search.include_friends) && user_id.is_none() {
+                return Err(Error::Forbidden(String::from("to include the current user or friends \
+                                                          the token must be a user scoped \
+                                                          token")));
+            }
+            let mut headers = Headers::new();
+            headers.set(Authorization(access_token.get_token()));
+            let dto = FilteredSearchUserDTO {
+                random: search.random.clone(),
+                username: search.username.clone(),
+                email: search.email.clone(),
+                min_sybil_score: search.min_sybil_score,
+                max_sybil_score: search.max_sybil_score,
+                min_trust_score: search.min_trust_score,
+                include_me: search.include_me,
+                include_friends: search.include_friends,
+            };
+            let mut response = self.send_request(Method::Post,
+                              format!("{}search_user", self.url),
+                              headers,
+                              Some(&dto))?;
+            self.decode_dto_list::<ProfileDTO, Profile>(&mut response)
+        } else {
+            Err(Error::Forbidden(String::from("the token must be an unexpired public, user or \
+                                               admin token")))
+        }
+    }
+
+    /// Initiates the identity verification of the logged in user
     pub fn initiate_user_verification(&self, 
                                         access_token: &AccessToken,
                                         dfp_value: String,
 										dft_value: String) 
-                                        -> Result<(ResponseDTO)> {
-        if access_token.get_user_id().is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Post,
-                            format!("{}initiate_user_verification/{}/{}",
-                                    self.url,
-                                    dfp_value,
+                                        -> Result<ApiMessage> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Post,
+                        format!("{}initiate_user_verification/{}/{}",
+                                self.url,
+                                dfp_value,
 									dft_value),
-                            headers,
-                            None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            let res: ResponseDTO = json::decode(&response_str)?;
-            match response.status {
-                StatusCode::Ok => {
-                    Ok(res)
-                }
-                _ => {
-                    Err(Error::Forbidden(json::decode::<ResponseDTO>(&response_str)?.message))
-                }
+                        headers,
+                        None::<&VoidDTO>)?;
+        self.decode_message(&mut response)
+    }
+
+    /// Gets the identity verification status of the logged in user
+    pub fn get_user_verification_status(&self,
+                                        access_token: &AccessToken)
+                                        -> Result<VerificationStatus> {
+        self.require_user(access_token)?;
+        let mut headers = Headers::new();
+        headers.set(Authorization(access_token.get_token()));
+        let mut response = self.send_request(Method::Get,
+                          format!("{}get_user_verification_status/{}",
+                                  self.url,
+                                  access_token.get_user_id().unwrap()),
+                          headers,
+                          None::<&VoidDTO>)?;
+        let status = self.decode_response::<ResponseDTO>(&mut response)?.message;
+        Ok(VerificationStatus::from(status.as_str()))
+    }
+
+    /// Waits until the identity verification of the logged in user finishes, and returns its
+    /// status
+    ///
+    /// It will poll the verification status every `poll_interval` until the user is verified or
+    /// rejected, or until the `timeout` elapses, returning `Error::Timeout` in that case.
+    pub fn wait_for_verification(&self,
+                                 access_token: &AccessToken,
+                                 timeout: Duration,
+                                 poll_interval: Duration)
+                                 -> Result<VerificationStatus> {
+        let start = Instant::now();
+        loop {
+            match self.get_user_verification_status(access_token)? {
+                status @ VerificationStatus::Verified |
+                status @ VerificationStatus::Rejected => return Ok(status),
+                _ => {}
             }
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(Error::Timeout);
+            }
+            let remaining = timeout - elapsed;
+            thread::sleep(if poll_interval < remaining {
+                poll_interval
+            } else {
+                remaining
+            });
         }
     }
 
-    /// Generates a new authenticator code, and returns the URL.
-    pub fn get_user_verification_status(&self, access_token: &AccessToken) -> Result<String> {
-        if access_token.get_user_id().is_some() && !access_token.has_expired() {
-            let mut headers = Headers::new();
-            headers.set(Authorization(access_token.get_token()));
-            let mut response = self.send_request(Method::Get,
-                              format!("{}get_user_verification_status/{}", self.url, access_token.get_user_id().unwrap()),
-                              headers,
-                              None::<&VoidDTO>)?;
-            let mut response_str = String::new();
-            let _ = response.read_to_string(&mut response_str)?;
-            Ok(json::decode::<ResponseDTO>(&response_str)?.message)
-        } else {
-            Err(Error::Forbidden(String::from("the token must be an unexpired user token")))
+}
+
+/// Number of users fetched per page by `Client::stream_all_users()`.
+pub const USER_PAGE_SIZE: usize = 100;
+
+/// User search query.
+///
+/// It is used with `Client::search_user()`, and built by chaining its methods:
+///
+/// ```
+/// use fractal_api::v1::UserSearch;
+///
+/// let search = UserSearch::new().random("john").max_sybil_score(10);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UserSearch {
+    random: Option<String>,
+    username: Option<String>,
+    email: Option<String>,
+    min_sybil_score: Option<i8>,
+    max_sybil_score: Option<i8>,
+    min_trust_score: Option<i8>,
+    include_me: bool,
+    include_friends: bool,
+}
+
+impl UserSearch {
+    /// Creates a new empty user search query.
+    pub fn new() -> UserSearch {
+        UserSearch::default()
+    }
+
+    /// Searches the given string in names, emails etc.
+    pub fn random<S: Into<String>>(mut self, random: S) -> UserSearch {
+        self.random = Some(random.into());
+        self
+    }
+
+    /// Searches the given username.
+    pub fn username<S: Into<String>>(mut self, username: S) -> UserSearch {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Searches the given email.
+    pub fn email<S: Into<String>>(mut self, email: S) -> UserSearch {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Only returns users with at least the given sybil score.
+    pub fn min_sybil_score(mut self, score: i8) -> UserSearch {
+        self.min_sybil_score = Some(score);
+        self
+    }
+
+    /// Only returns users with at most the given sybil score.
+    pub fn max_sybil_score(mut self, score: i8) -> UserSearch {
+        self.max_sybil_score = Some(score);
+        self
+    }
+
+    /// Only returns users with at least the given trust score.
+    pub fn min_trust_score(mut self, score: i8) -> UserSearch {
+        self.min_trust_score = Some(score);
+        self
+    }
+
+    /// Sets whether to include the current user in the results.
+    pub fn include_me(mut self, include_me: bool) -> UserSearch {
+        self.include_me = include_me;
+        self
+    }
+
+    /// Sets whether to include the friends of the current user in the results.
+    pub fn include_friends(mut self, include_friends: bool) -> UserSearch {
+        self.include_friends = include_friends;
+        self
+    }
+}
+
+/// Checks that the given email is not obviously malformed.
+///
+/// It only checks that there is a non-empty local part and domain separated by an `@`, without
+/// whitespace, leaving the full validation to the server.
+fn is_valid_email(email: &str) -> bool {
+    match email.rfind('@') {
+        Some(i) => {
+            let (local, domain) = (&email[..i], &email[i + 1..]);
+            !local.is_empty() && !domain.is_empty() && !domain.starts_with('.') &&
+            !domain.ends_with('.') && !email.chars().any(char::is_whitespace)
         }
+        None => false,
     }
+}
 
+/// Checks that the given phone is not obviously malformed.
+///
+/// International formats are accepted, so besides digits it allows a leading `+`, spaces, dots,
+/// dashes and parentheses.
+fn is_valid_phone(phone: &str) -> bool {
+    let phone = phone.trim();
+    let digits = phone.chars().filter(|c| c.is_digit(10)).count();
+    digits >= 4 &&
+    phone.chars().enumerate().all(|(i, c)| {
+        c.is_digit(10) || (c == '+' && i == 0) || c == ' ' || c == '.' || c == '-' ||
+        c == '(' || c == ')'
+    })
 }
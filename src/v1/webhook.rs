@@ -0,0 +1,90 @@
+//! Webhook module for the Fractal API.
+//!
+//! Contains the helpers required to verify and decode the webhooks sent by the API.
+
+use std::str;
+
+use crypto::hmac::Hmac;
+use crypto::mac::{Mac, MacResult};
+use crypto::sha2::Sha256;
+use rustc_serialize::{json, Decodable};
+use rustc_serialize::hex::FromHex;
+use dto::{FromDTO, TransactionDTO};
+
+use error::Result;
+use super::dtos::{WebhookDTO, VerificationWebhookDTO};
+use super::types::{Transaction, VerificationStatus};
+
+/// Name of the header containing the signature of the webhook.
+pub const SIGNATURE_HEADER: &'static str = "X-Fractal-Signature";
+
+/// Prefix of the signature in the signature header.
+const SIGNATURE_PREFIX: &'static str = "sha256=";
+
+/// Event received in a webhook.
+#[derive(Clone, Debug)]
+pub enum WebhookEvent {
+    /// A transaction has been completed.
+    Transaction(Transaction),
+    /// The verification status of a user has changed.
+    Verification {
+        /// ID of the user.
+        user_id: u64,
+        /// New verification status of the user.
+        status: VerificationStatus,
+    },
+    /// Unknown event, with its event type.
+    Unknown(String),
+}
+
+/// Verifies the signature of a webhook.
+///
+/// The signature is the hex encoded HMAC-SHA256 of the raw payload, using the webhook secret as
+/// key. The signature header might optionally be prefixed by `sha256=`. The comparison is done in
+/// constant time.
+pub fn verify_signature(secret: &[u8], payload: &[u8], signature_header: &str) -> bool {
+    let signature = signature_header.trim();
+    let signature = if signature.starts_with(SIGNATURE_PREFIX) {
+        &signature[SIGNATURE_PREFIX.len()..]
+    } else {
+        signature
+    };
+    let signature = match signature.from_hex() {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+
+    let mut hmac = Hmac::new(Sha256::new(), secret);
+    hmac.input(payload);
+    hmac.result() == MacResult::new(&signature)
+}
+
+/// Decodes the payload of a webhook into its event.
+///
+/// The signature of the payload should be checked with `verify_signature()` before decoding it.
+pub fn decode_event(payload: &[u8]) -> Result<WebhookEvent> {
+    let payload = match str::from_utf8(payload) {
+        Ok(p) => p,
+        Err(_) => {
+            return Err(json::DecoderError::ApplicationError(String::from("the webhook payload \
+                                                                          is not valid UTF-8"))
+                .into())
+        }
+    };
+    let webhook: WebhookDTO = json::decode(payload)?;
+    let mut decoder = json::Decoder::new(webhook.data);
+    match webhook.event.as_str() {
+        "transaction" => {
+            let dto: TransactionDTO = Decodable::decode(&mut decoder)?;
+            Ok(WebhookEvent::Transaction(Transaction::from_dto(dto)?))
+        }
+        "verification" => {
+            let dto: VerificationWebhookDTO = Decodable::decode(&mut decoder)?;
+            Ok(WebhookEvent::Verification {
+                user_id: dto.user_id,
+                status: VerificationStatus::from(dto.status.as_str()),
+            })
+        }
+        _ => Ok(WebhookEvent::Unknown(webhook.event)),
+    }
+}
@@ -0,0 +1,40 @@
+extern crate hyper;
+extern crate chrono;
+extern crate fractal_dto;
+extern crate fractal_api;
+
+mod common;
+
+use std::time::Duration;
+
+use hyper::header::Headers;
+use hyper::status::StatusCode;
+use fractal_api::error::Error;
+
+use common::MockTransport;
+
+#[test]
+fn redirected_probe_closes_the_circuit() {
+    let transport = MockTransport::new();
+    let mut client = transport.client();
+    client.enable_circuit_breaker(1, Duration::from_secs(0));
+
+    // The failure opens the circuit, and the next request is sent as the probe.
+    transport.respond(StatusCode::InternalServerError, "");
+    match client.ping() {
+        Err(Error::Server { .. }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    let mut headers = Headers::new();
+    headers.set_raw("Location", vec![b"/v1/elsewhere".to_vec()]);
+    transport.respond_with_headers(StatusCode::Found, headers, "");
+    match client.ping() {
+        Err(Error::TooManyRedirects) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+
+    // The server responded to the probe, so the circuit must be closed again.
+    transport.respond(StatusCode::Ok, "");
+    assert!(client.ping().is_ok());
+    assert_eq!(transport.requests().len(), 3);
+}
@@ -0,0 +1,113 @@
+//! Mock transport shared by the integration tests.
+#![allow(dead_code)]
+
+use std::io::{self, Cursor};
+use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+
+use chrono::{UTC, Duration};
+use hyper::method::Method;
+use hyper::header::Headers;
+use hyper::status::StatusCode;
+use fractal_api::Client;
+use fractal_api::v1::oauth::AccessToken;
+use fractal_dto::ScopeDTO as Scope;
+use fractal_api::error::{Result, Error};
+use fractal_api::v1::transport::{Transport, RawResponse};
+
+/// URL of the server used by the mocked clients.
+pub const SERVER: &'static str = "https://api.example.com/";
+
+/// Creates an unexpired access token with the given scopes.
+pub fn token(scopes: Vec<Scope>) -> AccessToken {
+    AccessToken::from_data("app", scopes, "token", UTC::now() + Duration::hours(1))
+}
+
+/// Creates an unexpired public token.
+pub fn public_token() -> AccessToken {
+    token(vec![Scope::Public])
+}
+
+/// Creates an unexpired user token for the given user.
+pub fn user_token(user_id: u64) -> AccessToken {
+    token(vec![Scope::User(user_id)])
+}
+
+/// Request received by the mock transport.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    pub url: String,
+    pub headers: Headers,
+    pub body: Option<Vec<u8>>,
+}
+
+/// Transport that records the requests it receives and answers them with the queued responses.
+///
+/// Once the queue is empty, every request fails as if the connection was refused. Clones share
+/// the same queue and record, so a clone can be given to the client and inspected later.
+#[derive(Clone, Default)]
+pub struct MockTransport {
+    requests: Arc<Mutex<Vec<Request>>>,
+    responses: Arc<Mutex<VecDeque<(StatusCode, Headers, Vec<u8>)>>>,
+}
+
+impl MockTransport {
+    /// Creates a mock transport without queued responses.
+    pub fn new() -> MockTransport {
+        MockTransport::default()
+    }
+
+    /// Queues a response with the given status and body.
+    pub fn respond<B: AsRef<[u8]>>(&self, status: StatusCode, body: B) {
+        self.respond_with_headers(status, Headers::new(), body);
+    }
+
+    /// Queues a response with the given status, headers and body.
+    pub fn respond_with_headers<B: AsRef<[u8]>>(&self,
+                                                status: StatusCode,
+                                                headers: Headers,
+                                                body: B) {
+        self.responses.lock().unwrap().push_back((status, headers, body.as_ref().to_vec()));
+    }
+
+    /// Gets the requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Gets the last request received.
+    pub fn last_request(&self) -> Request {
+        self.requests().pop().expect("no request was sent")
+    }
+
+    /// Creates a client that sends its requests through this transport.
+    pub fn client(&self) -> Client {
+        Client::with_transport(SERVER, self.clone()).unwrap()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self,
+            method: Method,
+            url: &str,
+            headers: Headers,
+            body: Option<&[u8]>)
+            -> Result<RawResponse> {
+        self.requests.lock().unwrap().push(Request {
+            method: method,
+            url: url.to_owned(),
+            headers: headers,
+            body: body.map(|b| b.to_vec()),
+        });
+        match self.responses.lock().unwrap().pop_front() {
+            Some((status, headers, body)) => {
+                Ok(RawResponse::new(status, headers, Cursor::new(body)))
+            }
+            None => {
+                Err(Error::IO(io::Error::new(io::ErrorKind::ConnectionRefused,
+                                             "no response queued")))
+            }
+        }
+    }
+}
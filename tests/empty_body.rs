@@ -0,0 +1,51 @@
+extern crate hyper;
+extern crate chrono;
+extern crate fractal_dto;
+extern crate fractal_api;
+
+mod common;
+
+use hyper::status::StatusCode;
+use fractal_api::v1::types::ApiMessage;
+
+use common::{MockTransport, user_token};
+
+#[test]
+fn empty_body_is_an_empty_message() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, "");
+    let message = client.unsubscribe_email_confirmation(&user_token(1)).unwrap();
+    assert_eq!(message, ApiMessage::default());
+    assert_eq!(message.message(), "");
+}
+
+#[test]
+fn blank_body_is_an_empty_message() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, " \r\n");
+    let message = client.unsubscribe_email_confirmation(&user_token(1)).unwrap();
+    assert_eq!(message.message(), "");
+}
+
+#[test]
+fn empty_body_is_no_optional_value() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, "");
+    assert!(client.get_referrer(&user_token(1), 1).unwrap().is_none());
+}
+
+#[test]
+fn message_body_is_decoded() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, r#"{"message":"sent"}"#);
+    let message = client.unsubscribe_email_confirmation(&user_token(1)).unwrap();
+    assert_eq!(message.message(), "sent");
+}
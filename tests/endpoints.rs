@@ -0,0 +1,126 @@
+extern crate hyper;
+extern crate chrono;
+extern crate fractal_dto;
+extern crate fractal_api;
+
+mod common;
+
+use hyper::method::Method;
+use hyper::status::StatusCode;
+use fractal_api::error::Error;
+use fractal_api::v1::types::VerificationStatus;
+
+use common::{MockTransport, SERVER, public_token, user_token};
+
+/// Asserts that the last request was sent with the given method to the given path of the API.
+fn assert_request(transport: &MockTransport, method: Method, path: &str) {
+    let request = transport.last_request();
+    assert_eq!(request.method, method);
+    assert_eq!(request.url, format!("{}v1/{}", SERVER, path));
+}
+
+#[test]
+fn confirm_email() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, r#"{"message":"confirmed"}"#);
+    let message = client.confirm_email(&public_token(), "key").unwrap();
+    assert_request(&transport, Method::Post, "confirm_email/key");
+    assert_eq!(message.message(), "confirmed");
+}
+
+#[test]
+fn unconfirm_email() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, r#"{"message":"unconfirmed"}"#);
+    let message = client.unconfirm_email(&public_token(), "key").unwrap();
+    assert_request(&transport, Method::Post, "unconfirm_email/key");
+    assert_eq!(message.message(), "unconfirmed");
+}
+
+#[test]
+fn start_reset_password() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, r#"{"message":"email sent"}"#);
+    let message = client.start_reset_password(&public_token(), "user@example.com").unwrap();
+    assert_request(&transport, Method::Post, "start_reset_password");
+    assert_eq!(message.message(), "email sent");
+    let body = String::from_utf8(transport.last_request().body.unwrap()).unwrap();
+    assert!(body.contains("user@example.com"));
+}
+
+#[test]
+fn subscribe_email() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, "");
+    client.subscribe_email(&public_token(), "user@example.com", "User").unwrap();
+    assert_request(&transport, Method::Post, "subscribe_email");
+    let body = String::from_utf8(transport.last_request().body.unwrap()).unwrap();
+    assert!(body.contains("user@example.com"));
+}
+
+#[test]
+fn confirm_subscribe_email() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, "");
+    client.confirm_subscribe_email(&public_token(), "key").unwrap();
+    assert_request(&transport, Method::Post, "confirm_subscribe_email/key");
+}
+
+#[test]
+fn get_next_reward_value() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, r#"{"message":"25"}"#);
+    let message = client.get_next_reward_value(&user_token(3), 3).unwrap();
+    assert_request(&transport, Method::Get, "get_next_reward/3");
+    assert_eq!(message.message(), "25");
+}
+
+#[test]
+fn get_user_by_name() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::NotFound, r#"{"message":"not found"}"#);
+    match client.get_user_by_name(&user_token(1), &String::from("alice")) {
+        Err(Error::NotFound(ref message)) if message == "not found" => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_request(&transport, Method::Get, "get_user_from_username/alice");
+}
+
+#[test]
+fn initiate_user_verification() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, r#"{"message":"started"}"#);
+    let message = client.initiate_user_verification(&user_token(1),
+                                    String::from("dfp"),
+                                    String::from("dft"))
+        .unwrap();
+    assert_request(&transport, Method::Post, "initiate_user_verification/dfp/dft");
+    assert_eq!(message.message(), "started");
+}
+
+#[test]
+fn get_user_verification_status() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    transport.respond(StatusCode::Ok, r#"{"message":"pending"}"#);
+    let status = client.get_user_verification_status(&user_token(7)).unwrap();
+    assert_request(&transport, Method::Get, "get_user_verification_status/7");
+    assert_eq!(status, VerificationStatus::Pending);
+}
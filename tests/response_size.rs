@@ -0,0 +1,38 @@
+extern crate hyper;
+extern crate chrono;
+extern crate fractal_dto;
+extern crate fractal_api;
+
+mod common;
+
+use hyper::status::StatusCode;
+use fractal_api::error::Error;
+
+use common::MockTransport;
+
+#[test]
+fn oversized_body_is_rejected() {
+    let transport = MockTransport::new();
+    let mut client = transport.client();
+    client.set_max_response_size(8);
+
+    transport.respond(StatusCode::Ok, r#"{"version":"1.0.0"}"#);
+    match client.get_server_info() {
+        Err(Error::ResponseTooLarge) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn character_cut_at_the_limit_is_reported_as_too_large() {
+    let transport = MockTransport::new();
+    let mut client = transport.client();
+    // Reading one byte past the limit cuts the second two byte character in half.
+    client.set_max_response_size(2);
+
+    transport.respond(StatusCode::Ok, "ñññ");
+    match client.get_server_info() {
+        Err(Error::ResponseTooLarge) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
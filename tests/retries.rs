@@ -0,0 +1,50 @@
+extern crate hyper;
+extern crate chrono;
+extern crate fractal_dto;
+extern crate fractal_api;
+
+mod common;
+
+use hyper::status::StatusCode;
+use fractal_api::error::Error;
+use fractal_api::v1::DEFAULT_MAX_ATTEMPTS;
+
+use common::{MockTransport, public_token};
+
+#[test]
+fn idempotent_requests_stop_retrying_after_the_maximum_attempts() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    match client.ping() {
+        Err(Error::IO(_)) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(transport.requests().len(), DEFAULT_MAX_ATTEMPTS as usize);
+}
+
+#[test]
+fn maximum_attempts_can_be_changed() {
+    let transport = MockTransport::new();
+    let mut client = transport.client();
+    client.set_max_attempts(5);
+
+    match client.ping() {
+        Err(Error::IO(_)) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(transport.requests().len(), 5);
+}
+
+#[test]
+fn non_idempotent_requests_are_not_retried() {
+    let transport = MockTransport::new();
+    let client = transport.client();
+
+    assert!(client.confirm_subscribe_email(&public_token(), "key").is_err());
+    assert_eq!(transport.requests().len(), 1);
+
+    transport.respond(StatusCode::Ok, "");
+    client.confirm_subscribe_email(&public_token(), "key").unwrap();
+    assert_eq!(transport.requests().len(), 2);
+}